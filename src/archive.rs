@@ -0,0 +1,99 @@
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// A file stored inside an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    /// Path of the file inside the archive
+    pub name: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+}
+
+/// Check if a path looks like an archive we can list
+/// Examples:
+/// foo.zip -> true
+/// foo.tar.gz -> true
+/// foo.txt -> false
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// Archive formats that we can list
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// List the files inside a zip, tar or tar.gz archive
+/// Directories inside the archive are left out, only files are listed
+pub fn list_archive(path: &Path) -> Result<Vec<ArchiveMember>, String> {
+    let kind = archive_kind(path).ok_or("Not a supported archive")?;
+    let file = File::open(path).map_err(|e| e.to_string())?;
+
+    match kind {
+        ArchiveKind::Zip => list_zip(file),
+        ArchiveKind::Tar => list_tar(BufReader::new(file)),
+        ArchiveKind::TarGz => list_tar(GzDecoder::new(BufReader::new(file))),
+    }
+}
+
+fn list_zip(file: File) -> Result<Vec<ArchiveMember>, String> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut members = Vec::new();
+
+    for i in 0..archive.len() {
+        // raw access only reads the headers, nothing gets decompressed
+        let member = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+        if member.is_dir() {
+            continue;
+        }
+
+        members.push(ArchiveMember {
+            name: member.name().map_err(|e| e.to_string())?.into_owned(),
+            size: member.size(),
+        });
+    }
+
+    Ok(members)
+}
+
+fn list_tar(reader: impl Read) -> Result<Vec<ArchiveMember>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+
+    for member in archive.entries().map_err(|e| e.to_string())? {
+        let member = member.map_err(|e| e.to_string())?;
+        if member.header().entry_type().is_dir() {
+            continue;
+        }
+
+        members.push(ArchiveMember {
+            name: member
+                .path()
+                .map_err(|e| e.to_string())?
+                .to_string_lossy()
+                .into_owned(),
+            size: member.size(),
+        });
+    }
+
+    Ok(members)
+}
@@ -1,9 +1,21 @@
-use clap::Parser;
+use clap::{ArgGroup, Parser};
+use clap_complete::Shell;
+use globset::Glob;
+use regex::Regex;
+use std::num::NonZeroUsize;
+use std::time::SystemTime;
 
-use crate::config::Unit;
+use crate::config::{
+    ColorMode, Column, Compression, Direction, ExtensionSort, Format, Rounding, ScaleColumn, Size,
+    SortExpr, SortKey, TimeField, TimeStyle, Unit, parse_block_size, parse_date, parse_glob,
+    parse_since, parse_sort_key,
+};
+use crate::exec::ExecCommand;
+use crate::template::Template;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
+#[command(group(ArgGroup::new("patterns").multiple(true)))]
 pub struct Args {
     /// Directory to view
     #[arg(default_value = "./")]
@@ -13,6 +25,17 @@ pub struct Args {
     #[arg(short = 'C', long)]
     pub canonicalize: bool,
 
+    /// Name every entry by its path relative to BASE, which doesn't have to be the
+    /// listed directory, e.g. "../src/main.rs". Entries that share nothing but the
+    /// root with BASE are shown by their absolute path
+    #[arg(long, value_name = "BASE", conflicts_with = "canonicalize")]
+    pub relative_to: Option<String>,
+
+    /// Put names too long for the name column on a line of their own, with the
+    /// other columns on the next line, instead of cutting them short with …
+    #[arg(long, alias = "no-ellipsis")]
+    pub wrap: bool,
+
     /// Maximum depth to traverse
     #[arg(short = 'd', long)]
     pub max_depth: Option<usize>,
@@ -20,12 +43,531 @@ pub struct Args {
     #[arg(short = 'H')]
     pub show_hidden: bool,
 
+    /// Show hidden files and directories, but don't walk into hidden directories
+    /// like .git, so what's inside them stays out of the listing
+    #[arg(long)]
+    pub no_recurse_hidden_dirs: bool,
+
     #[arg(short = 't', long)]
     pub table: bool,
 
-    #[arg(short = 'u', long, default_value = "bytes")]
+    /// Pick the columns of a row or table and their order, e.g. "name,size,perms,modified".
+    /// One of name, ext, date, created, modified, accessed, perms, size, scaled,
+    /// encoding, children, xattrs, share, drift, age, allocated, owner, flags or hash.
+    /// Columns that need extra work, like hash, are turned on by picking them
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub columns: Vec<Column>,
+
+    /// Unit for file sizes (bytes, kb, mb, gb, tb, auto), defaults to bytes
+    #[arg(short = 'u', long)]
     pub unit: Option<Unit>,
 
-    #[arg(short = 'r', long)]
+    /// Show sizes in bytes, same as -u b
+    #[arg(long, conflicts_with = "unit")]
+    pub bytes: bool,
+
+    /// How sizes in a fixed unit are brought down to a whole number: truncate drops
+    /// the remainder, so 1600 bytes in kib is 1, round goes to the nearest, making
+    /// it 2. Totals are always added up in bytes first, so they are exact either way
+    #[arg(long, value_name = "MODE", default_value = "truncate")]
+    pub size_rounding: Rounding,
+
+    /// Reverse the sort order, flipping every sort key
+    #[arg(short = 'r', long, visible_alias = "reverse")]
     pub reversed: bool,
+
+    /// Group recursive output by directory, printing a header per directory
+    #[arg(short = 'g', long)]
+    pub group_by_dir: bool,
+
+    /// Skip files larger than SIZE (e.g. 500mb, 2.5g) before doing any work on them
+    #[arg(long, value_name = "SIZE")]
+    pub skip_larger_than: Option<Size>,
+
+    /// Only list files of at least SIZE (e.g. 10kb, 1.5mb)
+    #[arg(long, value_name = "SIZE")]
+    pub min_size: Option<Size>,
+
+    /// Only list files of at most SIZE (e.g. 10kb, 1.5mb)
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<Size>,
+
+    /// Highlight the size of files of SIZE or more (e.g. 1g) in red, without
+    /// leaving anything out
+    #[arg(long, value_name = "SIZE")]
+    pub warn_size: Option<Size>,
+
+    /// Show two size columns, the exact bytes and the size in --unit, or in the
+    /// auto unit without one. Also applies to --total-only
+    #[arg(long, conflicts_with = "block_size")]
+    pub dual_units: bool,
+
+    /// Fastest listing: only names with type icons, without a single stat call, which
+    /// pays off most on network filesystems. The date, size and permission columns
+    /// are left out entirely, sorting by size or time falls back to the filesystem
+    /// order and filters on size, date or owner can't be used
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "min_size", "max_size", "skip_larger_than", "warn_size", "modified_after",
+            "modified_before", "owner", "group", "dir_size", "deref", "columns",
+            "show_mounts"
+        ]
+    )]
+    pub no_metadata: bool,
+
+    /// Only list files modified at or after DATE, as YYYY-MM-DD or
+    /// "YYYY-MM-DD HH:MM[:SS]" in local time
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub modified_after: Option<SystemTime>,
+
+    /// Only list files modified before DATE, as YYYY-MM-DD or
+    /// "YYYY-MM-DD HH:MM[:SS]" in local time
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub modified_before: Option<SystemTime>,
+
+    /// Mark entries modified after TIME with a yellow "*" at the end of the row,
+    /// without leaving anything out. TIME is a date like --modified-after takes,
+    /// or how long ago, e.g. 30m, 2h, 3d or 1w
+    #[arg(long, value_name = "TIME", value_parser = parse_since)]
+    pub since: Option<SystemTime>,
+
+    /// Only list entries whose name matches GLOB (e.g. "*.rs"), can be given more
+    /// than once. Directories that don't match are still walked into
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob, group = "patterns")]
+    pub glob: Vec<Glob>,
+
+    /// Only list entries whose name matches REGEX, together with --glob both have to match
+    #[arg(long, value_name = "REGEX", group = "patterns")]
+    pub regex: Option<Regex>,
+
+    /// Only list entries whose name fuzzy matches QUERY, its characters in order but
+    /// not next to each other, e.g. "cfgrs" matches config.rs. Without --sort the
+    /// best matches come first, matched characters are highlighted
+    #[arg(long, value_name = "QUERY")]
+    pub fuzzy: Option<String>,
+
+    /// Also list the N entries before and after every entry matching --glob or
+    /// --regex, dimmed, like grep -C
+    #[arg(long, value_name = "N", requires = "patterns")]
+    pub context: Option<usize>,
+
+    /// Leave out entries whose name matches GLOB, can be given more than once.
+    /// Matching directories are not walked into at all
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    pub exclude: Vec<Glob>,
+
+    /// Leave out .git, .hg, .svn and .bzr directories and everything in them,
+    /// like `tar --exclude-vcs`
+    #[arg(long)]
+    pub exclude_vcs: bool,
+
+    /// Only list files with one of these extensions, e.g. "rs,toml" or ".rs".
+    /// Can be given more than once
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    pub ext: Vec<String>,
+
+    /// Read extensions to list from a file, one per line, on top of any --ext.
+    /// Blank lines and lines starting with # are skipped
+    #[arg(long, value_name = "PATH")]
+    pub ext_file: Option<String>,
+
+    /// Only list entries owned by USER, a user name or a numeric uid
+    #[arg(long, value_name = "USER")]
+    pub owner: Option<String>,
+
+    /// Only list entries whose group is GROUP, a group name or a numeric gid
+    #[arg(long, value_name = "GROUP")]
+    pub group: Option<String>,
+
+    /// Match without regard to case everywhere text is matched: --glob, --exclude,
+    /// --regex, --ext, --fuzzy, --owner and --group
+    #[arg(short = 'i', long)]
+    pub ignore_case: bool,
+
+    /// Match --glob, --regex and --exclude against the path relative to the listed
+    /// directory instead of the file name, so patterns like "src/**/*.rs" work
+    #[arg(long)]
+    pub match_path: bool,
+
+    /// Show sizes as a number of SIZE blocks (e.g. 1k, 512, 1m), rounded up like
+    /// `du --block-size`, so a 1 byte file takes a whole block
+    #[arg(long, value_name = "SIZE", value_parser = parse_block_size, conflicts_with = "unit")]
+    pub block_size: Option<Size>,
+
+    /// Use powers of 1000 for size units instead of 1024, both for sizes given
+    /// on the command line and for the size column
+    #[arg(long)]
+    pub si: bool,
+
+    /// Sort entries by created, modified or accessed time, name, size, dirs (directories
+    /// first), or none to keep the filesystem order, which is faster for huge directories.
+    /// Add :desc to sort a key the other way, e.g. size:desc. Given more than once, the
+    /// keys are tried in order until one differs, e.g. --sort dirs --sort size:desc
+    /// --sort name. --reverse flips every key, including ones with :desc
+    #[arg(short = 's', long, value_name = "KEY[:DIR]", value_parser = parse_sort_key)]
+    pub sort: Vec<(SortKey, Direction)>,
+
+    /// Sort by several keys, e.g. "size desc, name asc". Conflicts with --sort, --reverse flips every key
+    #[arg(long, value_name = "EXPR", conflicts_with = "sort")]
+    pub sort_expr: Option<SortExpr>,
+
+    /// List directories before files within each directory, --reverse only flips
+    /// the order inside the two groups
+    #[arg(long)]
+    pub dirs_first: bool,
+
+    /// Guarantee the same order on every run: entries tied on the sort keys are
+    /// ordered by name, or by path across directories, instead of the order the
+    /// filesystem happens to return them in. Useful for snapshots and diffs
+    #[arg(long)]
+    pub stable: bool,
+
+    /// Descend into symlinks that point to directories, file symlinks are
+    /// listed but not followed. Links back into a parent directory are skipped
+    #[arg(long)]
+    pub follow_only_dirs: bool,
+
+    /// List the files in the trash with their original paths, the date column
+    /// shows when they were deleted
+    #[arg(long, conflicts_with = "dir")]
+    pub trash: bool,
+
+    /// List all entries at depth 1, then depth 2 and so on. Within each depth
+    /// entries are ordered by --sort across directories instead of per directory
+    #[arg(short = 'b', long)]
+    pub breadth_first: bool,
+
+    /// List entries by depth, shallowest first, and then by path. Unlike
+    /// --breadth-first, --sort is ignored and entries of one directory stay
+    /// together. --reverse flips the path order but keeps the depth order
+    #[arg(long, conflicts_with = "breadth_first")]
+    pub depth_sort: bool,
+
+    /// Compare the listing against OTHER_DIR, marking added (+), removed (-)
+    /// and changed (~) entries by relative path
+    #[arg(long, value_name = "OTHER_DIR")]
+    pub diff: Option<String>,
+
+    /// With --diff, print only how many entries were added, removed and changed,
+    /// e.g. "+12 -3 ~5"
+    #[arg(long, requires = "diff")]
+    pub diff_stat: bool,
+
+    /// Only list files changed since a git commit, branch or tag, compared with the working tree
+    #[arg(long, value_name = "REF")]
+    pub since_commit: Option<String>,
+
+    /// Timestamp to show in the date column (created, modified, accessed)
+    #[arg(short = 'T', long)]
+    pub time: Option<TimeField>,
+
+    /// Show - when there is no creation time, instead of falling back to the
+    /// modified (marked m) or accessed (marked a) time
+    #[arg(long)]
+    pub strict_created: bool,
+
+    /// Show the detected character encoding of text files
+    #[arg(long)]
+    pub encoding: bool,
+
+    /// Show how many entries each directory directly contains
+    #[arg(long)]
+    pub child_count: bool,
+
+    /// Show how many extended attributes each entry has, e.g. "@2", blank when none
+    #[arg(long)]
+    pub xattr: bool,
+
+    /// Show what share of its parent directory's total size each entry takes, e.g. "42%".
+    /// Directories count with the size of everything inside them
+    #[arg(long)]
+    pub percent: bool,
+
+    /// Show how long after its creation each file was last modified, e.g. "+2d".
+    /// A negative drift usually means a copy that kept its modification time
+    #[arg(long)]
+    pub show_drift: bool,
+
+    /// Show how old each entry is as a coarse bucket: today, this week, this month,
+    /// this year or older, going by the modification time. Quicker to scan than
+    /// exact dates when cleaning up
+    #[arg(long)]
+    pub age_bucket: bool,
+
+    /// Mark sparse files, whose allocated size on disk is much smaller than their
+    /// apparent size, e.g. VM images, and show how much of them is allocated
+    #[arg(long)]
+    pub sparse: bool,
+
+    /// Show the owner and group of each entry
+    #[arg(long)]
+    pub show_owner: bool,
+
+    /// Show the owner column with numeric uid and gid instead of names, like `ls -n`
+    #[arg(short = 'n', long)]
+    pub numeric_ids: bool,
+
+    /// Only list the ends of the tree: files, symlinks and empty directories.
+    /// Directories with anything in them, hidden entries included, are left out,
+    /// but their contents are still listed up to --max-depth
+    #[arg(long)]
+    pub leaves_only: bool,
+
+    /// Only list directories with no files at any depth, just more empty directories,
+    /// e.g. to find trees left behind after deleting files. Each tree is listed once,
+    /// by its top directory. Directories that can't be read all the way down are
+    /// never reported, since they may hold files
+    #[arg(long, conflicts_with = "flatten")]
+    pub prune_empty_report: bool,
+
+    /// List the files below the directory as one flat list, like `find -type f`:
+    /// directories are left out and every entry is named by its path relative to
+    /// DIR. Symlinks are kept, leave them out with --skip-symlinks
+    #[arg(long)]
+    pub flatten: bool,
+
+    /// Leave symlinks out of the listing
+    #[arg(long)]
+    pub skip_symlinks: bool,
+
+    /// Show the size, permissions and dates of a symlink's target instead of the
+    /// link itself, like ls -L. Broken links keep their own and get a red "!"
+    #[arg(short = 'L', long, conflicts_with = "skip_symlinks")]
+    pub deref: bool,
+
+    /// Put a type indicator after names like `ls -F`: "/" for directories, "@" for
+    /// symlinks, "=" for sockets, "|" for named pipes and "*" for executables
+    #[arg(short = 'F', long)]
+    pub classify: bool,
+
+    /// Mark directories that are mount points, on another device than their parent,
+    /// with the filesystem type after the name where it's known, e.g. "[mount: tmpfs]"
+    #[arg(long, visible_alias = "resolve-mounts")]
+    pub show_mounts: bool,
+
+    /// Show the start of the SHA-256 of each file's contents
+    #[arg(long)]
+    pub hash: bool,
+
+    /// Only list files with the same contents as another listed file, grouped by hash
+    #[arg(long)]
+    pub duplicates: bool,
+
+    /// Check files against a sha256sum style SUMS_FILE instead of listing, reporting
+    /// OK, FAILED or MISSING for each. Relative paths are looked up in the directory
+    #[arg(long, value_name = "SUMS_FILE", conflicts_with_all = ["trash", "from_stdin", "stdin_null"])]
+    pub verify: Option<String>,
+
+    /// Number of threads to hash files with, defaults to one per CPU
+    #[arg(short = 'j', long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Show lsattr style file flags, e.g. i for immutable and a for append only (Linux only)
+    #[arg(long)]
+    pub file_flags: bool,
+
+    /// Line up sizes with a fixed width number and a separate unit column, e.g. "  1.4 kib"
+    #[arg(short = 'A', long)]
+    pub align_sizes: bool,
+
+    /// Show dates relative to now, e.g. "5 minutes ago", same as --time-style relative
+    #[arg(long)]
+    pub relative_time: bool,
+
+    /// How to format dates: locale (the default), iso, long-iso, full-iso or relative
+    #[arg(long, value_name = "STYLE", conflicts_with = "relative_time")]
+    pub time_style: Option<TimeStyle>,
+
+    /// Nice defaults for interactive use: auto sizes, relative times, grouping
+    /// by directory and a name column that fits the terminal. Other options
+    /// still apply on top
+    #[arg(short = 'P', long)]
+    pub pretty: bool,
+
+    /// Spell out size units, e.g. "kilobytes" instead of "kib"
+    #[arg(long)]
+    pub long_units: bool,
+
+    /// Show the total size of directory contents instead of the directory entry size
+    #[arg(short = 'D', long)]
+    pub dir_size: bool,
+
+    /// Only count directory contents up to this many levels deep for --dir-size and
+    /// --total-only. Faster on huge trees, but a cut short total is only a lower
+    /// bound and gets a "+" after the unit, e.g. "3.2 gib+"
+    #[arg(long, value_name = "N")]
+    pub size_depth: Option<usize>,
+
+    /// Stop adding up a directory for --dir-size and --total-only once it's over
+    /// SIZE (e.g. 10g) and show "> SIZE" instead. Quick for spotting big trees,
+    /// but capped totals are approximate, so size sorting among them is too
+    #[arg(long, value_name = "SIZE")]
+    pub max_total_size: Option<Size>,
+
+    /// List the files inside .zip, .tar and .tar.gz archives, indented under the archive
+    #[arg(long)]
+    pub into_archives: bool,
+
+    /// Print extra details, like skipped symlink cycles, to stderr
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+
+    /// Don't print warnings, like unreadable directories, to stderr. Errors that
+    /// stop fview are still printed
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+
+    /// Print a completion script for SHELL (bash, zsh, fish, powershell, elvish) and exit
+    #[arg(long, value_name = "SHELL", hide = true)]
+    pub generate_completions: Option<Shell>,
+
+    /// List the paths read from stdin, one per line, instead of walking DIR,
+    /// e.g. `git ls-files | fview --from-stdin`
+    #[arg(long, conflicts_with_all = ["dir", "trash"])]
+    pub from_stdin: bool,
+
+    /// Like --from-stdin, but with paths separated by NUL bytes, as written by
+    /// `find -print0` or `fd -0`. Safe for names containing newlines
+    #[arg(short = '0', long, conflicts_with_all = ["dir", "trash"])]
+    pub stdin_null: bool,
+
+    /// Only print stats of the listing as one JSON object: totals, the largest and
+    /// newest file, and counts per extension
+    #[arg(long)]
+    pub summary_json: bool,
+
+    /// Only print the number of entries the listing would have, after all filters
+    #[arg(
+        long,
+        conflicts_with_all = ["summary_json", "extensions_summary", "ext_chart", "total_only", "diff"]
+    )]
+    pub count: bool,
+
+    /// Show at most N entries of each directory, followed by "… and M more", so one
+    /// huge directory doesn't take over a recursive listing. The N shown are the first
+    /// ones in --sort order
+    #[arg(long, value_name = "N", visible_alias = "max-results-per-dir")]
+    pub per_dir_limit: Option<NonZeroUsize>,
+
+    /// Indent JSON output from -f json and --summary-json, it is a single line otherwise
+    #[arg(long)]
+    pub json_pretty: bool,
+
+    /// Color the size and date columns on a gradient from the smallest to the largest
+    /// value in the listing, to spot outliers. Give a list like "size" to only color
+    /// some columns, the date column then keeps its usual coloring by age
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        num_args = 0..=1,
+        value_delimiter = ',',
+        default_missing_value = "size,date"
+    )]
+    pub color_scale: Option<Vec<ScaleColumn>>,
+
+    /// Only print each extension with its number of files and their total size,
+    /// ordered by count (the default) or by size
+    #[arg(
+        long,
+        value_name = "ORDER",
+        num_args = 0..=1,
+        default_missing_value = "count",
+        visible_alias = "uniq-extensions"
+    )]
+    pub extensions_summary: Option<ExtensionSort>,
+
+    /// Print a bar chart of the total bytes per file extension instead of the listing,
+    /// largest first. Only the top N extensions (10 by default) get their own bar,
+    /// the rest are added up under "(other)"
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        conflicts_with = "extensions_summary"
+    )]
+    pub ext_chart: Option<usize>,
+
+    /// Only print the total size of everything in the directory, like du -sh
+    #[arg(long)]
+    pub total_only: bool,
+
+    /// Don't print the absolute path of the listed directory above the listing
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Don't show file type icons, for terminals without a nerd font
+    #[arg(long)]
+    pub no_icons: bool,
+
+    /// When to use colors (auto, always, never), auto when neither this, FVIEW_COLOR
+    /// nor the profile sets it
+    #[arg(long, value_name = "WHEN")]
+    pub color: Option<ColorMode>,
+
+    /// Theme file (TOML or JSON) with icons and colors, defaults to theme.toml in the config dir
+    #[arg(long, value_name = "PATH")]
+    pub theme: Option<String>,
+
+    /// Load a named profile from the config file, CLI options override it
+    #[arg(short = 'p', long)]
+    pub profile: Option<String>,
+
+    /// Output format (text, json, ndjson, csv, table)
+    #[arg(short = 'f', long, default_value = "text")]
+    pub format: Format,
+
+    /// Print every entry as TEMPLATE instead, like `find -printf`, e.g.
+    /// "{name} {size} {mtime}". Placeholders are {name}, {path}, {size}, {bytes},
+    /// {perms}, {mtime}, {ctime} and {owner}. {{ and }} are literal braces, \t
+    /// and \n a tab and a newline
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = Template::parse,
+        conflicts_with_all = ["format", "table", "json_stream", "group_by_dir"]
+    )]
+    pub format_string: Option<Template>,
+
+    /// Stream the entries as newline delimited JSON, flushing every line, same as --format ndjson
+    #[arg(long, conflicts_with = "format")]
+    pub json_stream: bool,
+
+    /// Write the listing to a file instead of stdout, colors and icons are
+    /// left out unless --color=always
+    #[arg(short = 'o', long, value_name = "PATH")]
+    pub output: Option<String>,
+
+    /// Refuse to overwrite an existing --output file
+    #[arg(long, requires = "output")]
+    pub no_clobber: bool,
+
+    /// Compress the --output file with gz or zst, e.g. to keep big JSON or CSV
+    /// snapshots small. Picked from the file extension when not given, so
+    /// "-o snapshot.json.gz" is compressed with gzip
+    #[arg(long, value_name = "gz|zst", requires = "output")]
+    pub compress: Option<Compression>,
+
+    /// Redraw the listing every SECONDS until interrupted with Ctrl-C, clearing
+    /// the screen in between. Polls, so it works on any filesystem
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["output", "exec", "verify"]
+    )]
+    pub interval: Option<u64>,
+
+    /// Run CMD for every listed entry after printing, with {} replaced by the path.
+    /// End CMD with + to pass all paths to a single run, e.g. --exec "wc -l {} +",
+    /// split over several runs if they don't fit in one command line. CMD is split
+    /// on whitespace and quotes are not honored, so no argument can contain a space;
+    /// use a script for anything more involved
+    #[arg(short = 'x', long, value_name = "CMD", value_parser = ExecCommand::parse)]
+    pub exec: Option<ExecCommand>,
+
+    /// Only run --exec, without printing the listing
+    #[arg(long, requires = "exec")]
+    pub exec_only: bool,
 }
@@ -1,16 +1,52 @@
+use crate::archive::{self, ArchiveMember};
+#[cfg(feature = "cli")]
 use crate::cli::Args;
+#[cfg(feature = "cli")]
+use crate::config_file::{self, Profile};
+use crate::diff::{Change, DiffEntry, diff_listings};
+use crate::dir_size::dir_size;
+use crate::encoding;
+use crate::file_flags::file_flags;
+use crate::hash;
+use crate::log;
+use crate::mounts::{is_mount_point, mount_fs_type};
+use crate::owner::{file_ids, file_owner};
+#[cfg(feature = "cli")]
+use crate::owner::{parse_group, parse_user};
 use crate::string_ext;
+use crate::template::{Field, Template};
+use crate::theme::Theme;
+use crate::trash::{self, TrashItem};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
 use chrono_lc::LocaleDate;
 use colored::*;
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::ffi::OsString;
 use std::fs;
+use std::io::BufRead;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
 use std::str::FromStr;
-use std::time::SystemTime;
-use std::{error::Error, os::unix::fs::PermissionsExt};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 use string_ext::*;
+use unicode_segmentation::UnicodeSegmentation;
 use walkdir::{DirEntry, WalkDir};
 
+/// Locale used for dates and spelled out size units
+const LOCALE: &str = "";
+
 /// Configuration for viewing files
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -20,13 +56,191 @@ pub struct Config {
     pub max_depth: Option<usize>,
     /// If true, show canonicalized paths (absolute paths)
     pub canonicalize: bool,
+    /// Directory entry names are shown relative to, None to show them as listed
+    pub relative_to: Option<String>,
+    /// If true, names too long for the name column are put on a line of their own
+    /// instead of being cut short
+    pub wrap: bool,
     /// If true, show hidden files (files starting with a dot)
     pub show_hidden: bool,
+    /// If true, show hidden files but don't walk into hidden directories
+    pub no_recurse_hidden_dirs: bool,
     /// If true, render output as a table
     pub table: bool,
-    /// Unit for file sizes
+    /// Columns of a row or table in order, empty for the default layout
+    pub columns: Vec<Column>,
+    /// Unit for file sizes, bytes when None
     pub unit: Option<Unit>,
+    /// How sizes without decimals are rounded to a whole unit
+    pub size_rounding: Rounding,
     pub reversed: bool,
+    /// If true, group entries under a header per parent directory
+    pub group_by_dir: bool,
+    /// Files larger than this many bytes are pruned from the listing
+    pub skip_larger_than: Option<u64>,
+    /// Files smaller than this many bytes are left out of the listing
+    pub min_size: Option<u64>,
+    /// Files larger than this many bytes are left out of the listing
+    pub max_size: Option<u64>,
+    /// Files of at least this many bytes get their size highlighted as a warning
+    pub warn_size: Option<u64>,
+    /// If true, show the exact bytes and a scaled size in two size columns
+    pub dual_units: bool,
+    /// If true, no metadata is read at all and only names with type icons are listed
+    pub no_metadata: bool,
+    /// Files last modified before this are left out of the listing
+    pub modified_after: Option<SystemTime>,
+    /// Files last modified at or after this are left out of the listing
+    pub modified_before: Option<SystemTime>,
+    /// Entries modified after this time are marked in the listing, None to mark nothing
+    pub since: Option<SystemTime>,
+    /// Only entries matching one of these globs are listed, empty to list everything
+    pub globs: Vec<GlobMatcher>,
+    /// Only entries matching this regex are listed
+    pub regex: Option<Regex>,
+    /// Only entries whose name fuzzy matches this query are listed, None for all of them
+    pub fuzzy: Option<String>,
+    /// Entries matching one of these globs are pruned, directories included
+    pub exclude: Vec<GlobMatcher>,
+    /// If true, version control directories like .git are not listed or walked into
+    pub exclude_vcs: bool,
+    /// If true, patterns match the path relative to the listed directory instead of the file name
+    pub match_path: bool,
+    /// If true, extensions and the fuzzy query match regardless of case. Globs and
+    /// the regex are built case insensitive from the command line already
+    pub ignore_case: bool,
+    /// Only entries with one of these extensions are listed, without the dot
+    pub extensions: Vec<String>,
+    /// Only entries owned by this uid are listed
+    pub user: Option<u32>,
+    /// Only entries owned by this gid are listed
+    pub group: Option<u32>,
+    /// If true, size units are powers of 1000 instead of 1024
+    pub si: bool,
+    /// Show sizes as a count of blocks of this many bytes, rounded up
+    pub block_size: Option<u64>,
+    /// Key to sort entries by
+    pub sort: Option<SortKey>,
+    /// Sort keys with directions, overrides sort when set
+    pub sort_expr: Option<SortExpr>,
+    /// If true, directories are listed before files, also when reversed
+    pub dirs_first: bool,
+    /// If true, ties between sort keys are broken by name, for a deterministic order
+    pub stable: bool,
+    /// If true, list entries level by level instead of depth first
+    pub breadth_first: bool,
+    /// If true, list entries by depth and then by path, overrides breadth_first
+    pub depth_sort: bool,
+    /// Other directory to compare the listing against
+    pub diff: Option<String>,
+    /// If true, the diff is only summed up as counts of added, removed and changed entries
+    pub diff_stat: bool,
+    /// Which timestamp to show in the date column
+    pub time: Option<TimeField>,
+    /// If true, don't fall back to other timestamps when there is no creation time
+    pub strict_created: bool,
+    /// If true, show the detected character encoding of text files
+    pub encoding: bool,
+    /// If true, show how many entries each directory contains
+    pub child_count: bool,
+    /// If true, show how many extended attributes each entry has
+    pub xattr: bool,
+    /// If true, show the share of the parent directory's size of each entry
+    pub percent: bool,
+    /// If true, show how long after its creation each file was last modified
+    pub drift: bool,
+    /// If true, show how old each entry is as a coarse bucket like "this week"
+    pub age_bucket: bool,
+    /// If true, mark sparse files and show how much of them is allocated on disk
+    pub sparse: bool,
+    /// If true, show the owner and group of each entry
+    pub owner: bool,
+    /// If true, owners are shown as uid and gid instead of names
+    pub numeric_ids: bool,
+    /// If true, show the lsattr style file flags, Linux only
+    pub file_flags: bool,
+    /// If true, only list entries without children, files and empty directories
+    pub leaves_only: bool,
+    /// If true, only list the top directories of trees without any files in them
+    pub empty_trees: bool,
+    /// If true, directories are left out and entries are named by their relative path
+    pub flatten: bool,
+    /// If true, symlinks are left out of the listing
+    pub skip_symlinks: bool,
+    /// If true, names end in a type indicator: / @ = | or *
+    pub classify: bool,
+    /// If true, mount points are marked with their filesystem type after the name
+    pub show_mounts: bool,
+    /// If true, show a hash of each file's contents
+    pub hash: bool,
+    /// If true, only list files whose contents match another listed file
+    pub duplicates: bool,
+    /// Number of threads used for hashing, one per CPU when None
+    pub jobs: Option<usize>,
+    /// If true, descend into symlinks that point to directories
+    pub follow_only_dirs: bool,
+    /// If true, list the trash instead of the directory
+    pub trash: bool,
+    /// Paths to list instead of walking the directory, e.g. read from stdin
+    pub paths: Option<Vec<PathBuf>>,
+    /// If true, print only aggregate stats of the listing as a JSON object
+    pub summary_json: bool,
+    /// If true, print only the number of entries in the listing
+    pub count: bool,
+    /// Show at most this many entries per directory in text output
+    pub per_dir_limit: Option<usize>,
+    /// If true, JSON output is indented instead of on a single line
+    pub json_pretty: bool,
+    /// Columns colored on a gradient from the smallest to the largest value in the listing
+    pub color_scale: Vec<ScaleColumn>,
+    /// Ends of the gradient, found once the listing is collected
+    pub scale_range: ScaleRange,
+    /// Print only the file count and size per extension, in this order
+    pub extensions_summary: Option<ExtensionSort>,
+    /// Print a bar chart of the bytes per extension for the top N extensions instead of the listing
+    pub ext_chart: Option<usize>,
+    /// Number of entries to list before and after every entry matching the patterns
+    pub context: Option<usize>,
+    /// If true, symlinks show the size, permissions and dates of their target
+    pub deref: bool,
+    /// Width of the terminal the listing is written to, None when not writing to one
+    pub terminal_width: Option<usize>,
+    /// If true, print only the total size of the listed directory
+    pub total_only: bool,
+    /// If true, print the absolute path of the listed directory above text listings
+    pub header: bool,
+    /// If true, don't prefix file names with an icon
+    pub no_icons: bool,
+    /// If true, pad sizes so numbers and unit suffixes line up in their own sub-columns
+    pub align_sizes: bool,
+    /// How dates in the date column are formatted
+    pub time_style: TimeStyle,
+    /// Visible width of the name column, the default width is used when None
+    pub name_width: Option<usize>,
+    /// If true, spell out size units (e.g. "kilobytes" instead of "kib")
+    pub long_units: bool,
+    /// If true, show the recursive size of directories instead of the directory entry size
+    pub dir_size: bool,
+    /// How many levels below a directory dir_size counts, None for all of them
+    pub size_depth: Option<usize>,
+    /// Bytes after which dir_size stops counting a directory, None to always count it all
+    pub max_total_size: Option<u64>,
+    /// If true, list the files inside zip and tar archives under the archive
+    pub into_archives: bool,
+    /// If true, report extra details such as skipped symlink cycles on stderr
+    pub verbose: bool,
+    /// If true, warnings are not printed, only fatal errors
+    pub quiet: bool,
+    /// When to use colors
+    pub color: ColorMode,
+    /// Icons and colors for file names
+    pub theme: Theme,
+    /// Only list these files and the directories containing them, set from --since-commit
+    pub changed_paths: Option<HashSet<PathBuf>>,
+    /// Output format
+    pub format: Format,
+    /// Template every entry is printed with instead of the format, None to use the format
+    pub format_string: Option<Template>,
 }
 
 impl Default for Config {
@@ -35,42 +249,334 @@ impl Default for Config {
             dir: "./".to_string(),
             max_depth: None,
             canonicalize: false,
+            relative_to: None,
+            wrap: false,
             show_hidden: false,
+            no_recurse_hidden_dirs: false,
             table: false,
-            unit: Some(Unit::Bytes),
+            columns: Vec::new(),
+            unit: None,
+            size_rounding: Rounding::Truncate,
             reversed: false,
+            group_by_dir: false,
+            skip_larger_than: None,
+            min_size: None,
+            max_size: None,
+            warn_size: None,
+            dual_units: false,
+            no_metadata: false,
+            modified_after: None,
+            modified_before: None,
+            since: None,
+            globs: Vec::new(),
+            regex: None,
+            fuzzy: None,
+            exclude: Vec::new(),
+            exclude_vcs: false,
+            match_path: false,
+            ignore_case: false,
+            extensions: Vec::new(),
+            user: None,
+            group: None,
+            block_size: None,
+            si: false,
+            sort: Some(SortKey::Created),
+            sort_expr: None,
+            dirs_first: false,
+            stable: false,
+            breadth_first: false,
+            depth_sort: false,
+            diff: None,
+            diff_stat: false,
+            time: Some(TimeField::Created),
+            strict_created: false,
+            encoding: false,
+            child_count: false,
+            xattr: false,
+            percent: false,
+            drift: false,
+            age_bucket: false,
+            sparse: false,
+            owner: false,
+            numeric_ids: false,
+            file_flags: false,
+            leaves_only: false,
+            empty_trees: false,
+            flatten: false,
+            skip_symlinks: false,
+            classify: false,
+            show_mounts: false,
+            hash: false,
+            duplicates: false,
+            jobs: None,
+            follow_only_dirs: false,
+            trash: false,
+            paths: None,
+            summary_json: false,
+            count: false,
+            per_dir_limit: None,
+            json_pretty: false,
+            color_scale: Vec::new(),
+            scale_range: ScaleRange::default(),
+            extensions_summary: None,
+            ext_chart: None,
+            context: None,
+            deref: false,
+            terminal_width: None,
+            total_only: false,
+            header: true,
+            no_icons: false,
+            align_sizes: false,
+            time_style: TimeStyle::Locale,
+            name_width: None,
+            long_units: false,
+            dir_size: false,
+            size_depth: None,
+            max_total_size: None,
+            into_archives: false,
+            verbose: false,
+            quiet: false,
+            color: ColorMode::Auto,
+            theme: Theme::default(),
+            changed_paths: None,
+            format: Format::Text,
+            format_string: None,
         }
     }
 }
 
+#[cfg(feature = "cli")]
 impl From<Args> for Config {
     fn from(args: Args) -> Self {
+        // --pretty only fills in options that weren't given on the command line
+        let pretty = args.pretty;
+        // picking a column turns on whatever it needs, like hashing for the hash column
+        let column = |column: Column| args.columns.contains(&column);
+
         Config {
             dir: args.dir,
             max_depth: args.max_depth,
             canonicalize: args.canonicalize,
+            relative_to: args.relative_to,
+            wrap: args.wrap,
             show_hidden: args.show_hidden,
+            no_recurse_hidden_dirs: args.no_recurse_hidden_dirs,
             table: args.table,
-            unit: args.unit,
+            columns: args.columns.clone(),
+            unit: args
+                .unit
+                .or(args.bytes.then_some(Unit::Bytes))
+                .or(pretty.then_some(Unit::Auto)),
+            size_rounding: args.size_rounding,
             reversed: args.reversed,
+            group_by_dir: args.group_by_dir || pretty,
+            skip_larger_than: args.skip_larger_than.map(|size| size.bytes(args.si)),
+            min_size: args.min_size.map(|size| size.bytes(args.si)),
+            max_size: args.max_size.map(|size| size.bytes(args.si)),
+            warn_size: args.warn_size.map(|size| size.bytes(args.si)),
+            dual_units: args.dual_units || column(Column::Scaled),
+            no_metadata: args.no_metadata,
+            modified_after: args.modified_after,
+            modified_before: args.modified_before,
+            since: args.since,
+            globs: args
+                .glob
+                .iter()
+                .map(|glob| compile_glob(glob, args.ignore_case))
+                .collect(),
+            regex: args
+                .regex
+                .map(|regex| compile_regex(regex, args.ignore_case)),
+            fuzzy: args.fuzzy,
+            exclude: args
+                .exclude
+                .iter()
+                .map(|glob| compile_glob(glob, args.ignore_case))
+                .collect(),
+            exclude_vcs: args.exclude_vcs,
+            match_path: args.match_path,
+            ignore_case: args.ignore_case,
+            extensions: args
+                .ext
+                .iter()
+                .map(|ext| normalize_extension(ext))
+                .collect(),
+            // unknown names are reported by merge_config, here they just match nothing
+            user: args
+                .owner
+                .as_deref()
+                .and_then(|user| parse_user(user, args.ignore_case).ok()),
+            group: args
+                .group
+                .as_deref()
+                .and_then(|group| parse_group(group, args.ignore_case).ok()),
+            block_size: args.block_size.map(|size| size.bytes(args.si).max(1)),
+            si: args.si,
+            sort: args.sort.first().map(|&(key, _)| key),
+            // repeated --sort keys are a sort expression given one key at a time
+            sort_expr: args.sort_expr.or_else(|| match args.sort.as_slice() {
+                [] | [(_, Direction::Asc)] => None,
+                keys => Some(SortExpr(keys.to_vec())),
+            }),
+            dirs_first: args.dirs_first,
+            stable: args.stable,
+            breadth_first: args.breadth_first,
+            depth_sort: args.depth_sort,
+            diff: args.diff,
+            diff_stat: args.diff_stat,
+            time: args.time,
+            strict_created: args.strict_created,
+            encoding: args.encoding || column(Column::Encoding),
+            child_count: args.child_count || column(Column::Children),
+            xattr: args.xattr || column(Column::Xattrs),
+            percent: args.percent || column(Column::Share),
+            drift: args.show_drift || column(Column::Drift),
+            age_bucket: args.age_bucket || column(Column::Age),
+            sparse: args.sparse || column(Column::Allocated),
+            owner: args.show_owner || args.numeric_ids || column(Column::Owner),
+            numeric_ids: args.numeric_ids,
+            file_flags: args.file_flags || column(Column::Flags),
+            leaves_only: args.leaves_only,
+            empty_trees: args.prune_empty_report,
+            flatten: args.flatten,
+            skip_symlinks: args.skip_symlinks,
+            classify: args.classify,
+            show_mounts: args.show_mounts,
+            hash: args.hash || column(Column::Hash),
+            duplicates: args.duplicates,
+            jobs: args.jobs,
+            follow_only_dirs: args.follow_only_dirs,
+            trash: args.trash,
+            paths: None,
+            summary_json: args.summary_json,
+            count: args.count,
+            per_dir_limit: args.per_dir_limit.map(std::num::NonZeroUsize::get),
+            json_pretty: args.json_pretty,
+            color_scale: args.color_scale.unwrap_or_default(),
+            scale_range: ScaleRange::default(),
+            extensions_summary: args.extensions_summary,
+            ext_chart: args.ext_chart,
+            context: args.context,
+            deref: args.deref,
+            terminal_width: None,
+            total_only: args.total_only,
+            header: !args.no_header,
+            no_icons: args.no_icons,
+            align_sizes: args.align_sizes,
+            time_style: args.time_style.unwrap_or(if args.relative_time || pretty {
+                TimeStyle::Relative
+            } else {
+                TimeStyle::Locale
+            }),
+            name_width: None,
+            long_units: args.long_units,
+            dir_size: args.dir_size,
+            size_depth: args.size_depth,
+            max_total_size: args.max_total_size.map(|size| size.bytes(args.si)),
+            into_archives: args.into_archives,
+            verbose: args.verbose,
+            quiet: args.quiet,
+            color: args.color.unwrap_or_default(),
+            theme: Theme::default(),
+            changed_paths: None,
+            format: if args.json_stream {
+                Format::Ndjson
+            } else {
+                args.format
+            },
+            format_string: args.format_string,
+        }
+    }
+}
+
+/// Build the config from every source, each one overriding the ones before it:
+/// 1. the built-in defaults
+/// 2. the profile from the config file, when --profile is given
+/// 3. FVIEW_* environment variables
+/// 4. the command line
+///
+/// The theme and --ext-file are loaded as well, and the directory is made absolute
+#[cfg(feature = "cli")]
+pub fn resolve_config(args: Args) -> Result<Config, String> {
+    let env = Profile::from_env(std::env::vars())?;
+    let file = match &args.profile {
+        Some(name) => Some(config_file::load_profile(name)?),
+        None => None,
+    };
+    let theme = crate::theme::load_theme(args.theme.as_deref())?;
+    let ext_file = args.ext_file.clone();
+
+    let mut config = merge_config(args, &env, file.as_ref())?;
+    config.theme = theme;
+
+    if let Some(path) = ext_file {
+        config.extensions.extend(read_extension_file(&path)?);
+    }
+
+    config.dir = absolute_dir(&config.dir);
+    config.relative_to = config.relative_to.as_deref().map(absolute_dir);
+
+    Ok(config)
+}
+
+/// Make the listed directory absolute by joining a relative one onto the
+/// current working directory, so the header and anything working on full paths
+/// are right no matter how the directory was given. Symlinks are left as they
+/// are, and names in the listing stay relative unless canonicalized
+/// If the current directory can't be determined the directory is kept as given
+/// Examples, from /home/user:
+/// "./" -> "/home/user"
+/// "." -> "/home/user"
+/// "src" -> "/home/user/src"
+/// "/tmp" -> "/tmp"
+pub fn absolute_dir(dir: &str) -> String {
+    match std::path::absolute(dir) {
+        Ok(path) => path.to_string_lossy().into_owned(),
+        Err(e) => {
+            log::warn(format!("Error getting current directory: {}", e));
+            dir.to_string()
         }
     }
 }
 
+/// Merge the command line, environment and config file options in order of precedence
+/// The sources are applied from the config file to the command line, each one
+/// overriding the options the sources before it set
+#[cfg(feature = "cli")]
+pub fn merge_config(args: Args, env: &Profile, file: Option<&Profile>) -> Result<Config, String> {
+    // names are looked up once -i is known, an unknown one is an error
+    if let Some(user) = &args.owner {
+        parse_user(user, args.ignore_case)?;
+    }
+    if let Some(group) = &args.group {
+        parse_group(group, args.ignore_case)?;
+    }
+
+    let cli = Profile::from(&args);
+    let mut config = Config::from(args);
+    for source in [file, Some(env), Some(&cli)].into_iter().flatten() {
+        source.apply(&mut config);
+    }
+    Ok(config)
+}
+
 /// File size units that we support
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Unit {
+    #[default]
     Bytes,
     KB,
     MB,
     GB,
     TB,
+    /// Pick the largest unit that keeps the size at 1 or more, per file
+    Auto,
 }
 
 impl FromStr for Unit {
     type Err = String;
     /// Parse a string into a Unit enum
-    /// Supports: b, bytes, k, kb, kib, m, mb, mib, g, gb, gib, t, tb, tib
+    /// Supports: b, bytes, k, kb, kib, m, mb, mib, g, gb, gib, t, tb, tib, auto
     /// Examples:
     /// "b" -> Unit::Bytes
     /// "kb" -> Unit::KB
@@ -84,264 +590,3777 @@ impl FromStr for Unit {
             "m" | "mb" | "mib" => Ok(Unit::MB),
             "g" | "gb" | "gib" => Ok(Unit::GB),
             "t" | "tb" | "tib" => Ok(Unit::TB),
+            "auto" => Ok(Unit::Auto),
             _ => Err(format!("Invalid unit: {}", s)),
         }
     }
 }
 
-/// Normalize the unit to a short string representation
-/// Examples:
-/// Unit::Bytes -> "b"
-/// Unit::KB -> "kib"
-pub fn normalize_size_unit(unit: &Unit) -> &str {
-    match unit {
-        Unit::Bytes => "b",
-        Unit::KB => "kib",
-        Unit::MB => "mib",
-        Unit::GB => "gib",
-        Unit::TB => "tib",
+impl Unit {
+    /// Number of bytes in one of this unit
+    /// Auto has no fixed size, resolve it first
+    /// Examples:
+    /// Unit::Bytes -> 1
+    /// Unit::KB -> 1024
+    pub fn multiplier(&self) -> u64 {
+        match self {
+            Unit::Bytes | Unit::Auto => 1,
+            Unit::KB => 1024,
+            Unit::MB => 1024 * 1024,
+            Unit::GB => 1024 * 1024 * 1024,
+            Unit::TB => 1024 * 1024 * 1024 * 1024,
+        }
     }
-}
 
-/// View files in a directory based on the provided configuration
-/// If no configuration is provided, the default configuration is used
-pub fn view_files(config: Option<Config>) {
-    let config = config.unwrap_or_default();
+    /// Get the concrete unit to show a size in
+    /// Auto picks the largest unit that keeps the size at 1 or more, other units stay as they are
+    /// Examples:
+    /// (Unit::Auto, 512) -> Unit::Bytes
+    /// (Unit::Auto, 1536) -> Unit::KB
+    /// (Unit::MB, 1536) -> Unit::MB
+    pub fn resolve(&self, bytes: u64, si: bool) -> Unit {
+        if !matches!(self, Unit::Auto) {
+            return self.clone();
+        }
 
-    let depth = config.max_depth.unwrap_or(1);
-    let canonicalize = config.canonicalize;
+        [Unit::TB, Unit::GB, Unit::MB, Unit::KB]
+            .into_iter()
+            .find(|unit| bytes >= unit.bytes(si))
+            .unwrap_or(Unit::Bytes)
+    }
 
-    let unit = config.unit.unwrap_or(Unit::Bytes);
+    /// Number of bytes in one of this unit, in powers of 1000 if si is true
+    /// Examples:
+    /// (Unit::KB, false) -> 1024
+    /// (Unit::KB, true) -> 1000
+    pub fn bytes(&self, si: bool) -> u64 {
+        if !si {
+            return self.multiplier();
+        }
 
-    let walker = WalkDir::new(config.dir)
-        .min_depth(1)
-        .max_depth(depth)
-        .sort_by(move |a, b| {
-            let a_metadata = a.metadata().ok();
-            let b_metadata = b.metadata().ok();
-
-            let a_created = a_metadata
-                .as_ref()
-                .and_then(|m| m.created().ok())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
-
-            let b_created = b_metadata
-                .as_ref()
-                .and_then(|m| m.created().ok())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
-
-            if config.reversed {
-                return b_created.cmp(&a_created);
-            }
+        match self {
+            Unit::Bytes | Unit::Auto => 1,
+            Unit::KB => 1000,
+            Unit::MB => 1000 * 1000,
+            Unit::GB => 1000 * 1000 * 1000,
+            Unit::TB => 1000 * 1000 * 1000 * 1000,
+        }
+    }
+}
 
-            a_created.cmp(&b_created)
-        });
+/// How a size is brought down to a whole number of a unit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Rounding {
+    /// Drop the remainder, 1500 bytes in kib is 1
+    #[default]
+    Truncate,
+    /// Round to the nearest whole unit, halves up, 1500 bytes in kib is 1 and 1600 is 2
+    Nearest,
+}
 
-    let entries = walker
-        .into_iter()
-        .filter_entry(|e| config.show_hidden || !is_hidden(e));
+impl FromStr for Rounding {
+    type Err = String;
+    /// Parse a string into a Rounding enum
+    /// Examples:
+    /// "truncate" -> Rounding::Truncate
+    /// "round" -> Rounding::Nearest
+    /// "invalid" -> Err("Invalid rounding: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "truncate" | "floor" => Ok(Rounding::Truncate),
+            "round" | "nearest" => Ok(Rounding::Nearest),
+            _ => Err(format!("Invalid rounding: {}", s)),
+        }
+    }
+}
 
-    for entry in entries {
-        let entry = match entry {
-            Ok(e) => e,
-            Err(e) => {
-                eprintln!("{}", e.source().map(|c| c.to_string()).unwrap_or_default());
-                continue;
+impl Rounding {
+    /// Divide a number of bytes into whole units
+    /// Examples:
+    /// (Rounding::Truncate, 1600, 1024) -> 1
+    /// (Rounding::Nearest, 1600, 1024) -> 2
+    pub fn divide(self, bytes: u64, unit_bytes: u64) -> u64 {
+        match self {
+            Rounding::Truncate => bytes / unit_bytes,
+            Rounding::Nearest => {
+                bytes / unit_bytes + u64::from(bytes % unit_bytes >= unit_bytes.div_ceil(2))
             }
-        };
-
-        if config.table {
-            let table_entries = vec![entry];
-            let table = render_as_table(table_entries, canonicalize, &unit);
-            println!("{}", table);
-            continue;
-        } else {
-            println!("{}", render_as_row(entry, canonicalize, &unit));
         }
     }
 }
 
-/// Get the file name with an icon
-/// If canonicalize is true, return the canonicalized path
-/// Otherwise, return just the file name
-fn get_file_name(entry: walkdir::DirEntry, canonicalize: bool) -> Result<String, Box<dyn Error>> {
-    let name = entry.file_name();
-    let name = match name.to_str() {
-        Some(n) => n,
-        None => {
-            return Err("Failed to convert file name to string".into());
+/// A size as given on the command line, like "2.5gb"
+/// It is kept as a number and a unit until we know if --si is on
+#[derive(Debug, Clone)]
+pub struct Size {
+    number: f64,
+    unit: Unit,
+}
+
+impl FromStr for Size {
+    type Err = String;
+    /// Parse a number with an optional unit suffix
+    /// Examples:
+    /// "512" -> 512 bytes
+    /// "2.5gb" -> 2.5 gigabytes
+    /// "1.2.3kb" -> Err("Invalid size: 1.2.3kb, expected a number with an optional unit like 10mb")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            format!(
+                "Invalid size: {}, expected a number with an optional unit like 10mb",
+                s
+            )
+        };
+
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let unit = match unit.trim() {
+            "" => Unit::Bytes,
+            unit => Unit::from_str(unit).map_err(|_| invalid())?,
+        };
+        if matches!(unit, Unit::Auto) {
+            return Err(invalid());
         }
-    };
 
-    let name = if canonicalize {
-        entry
-            .path()
-            .canonicalize()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
-    } else {
-        name.to_string()
-    };
+        // SI sizes are never larger than binary ones, so checking binary covers both
+        if number * unit.multiplier() as f64 > u64::MAX as f64 {
+            return Err(format!("Size too large: {}", s));
+        }
 
-    let icon = get_file_icon(entry);
+        Ok(Size { number, unit })
+    }
+}
 
-    Ok(format!("{icon} {name}"))
+impl Size {
+    /// Get the size in bytes, rounded to the nearest byte
+    /// Examples:
+    /// ("2.5gb", false) -> 2684354560
+    /// ("2.5gb", true) -> 2500000000
+    pub fn bytes(&self, si: bool) -> u64 {
+        (self.number * self.unit.bytes(si) as f64).round() as u64
+    }
 }
 
-/// Get an icon based on the file type
-/// Directory:  (blue)
-/// Directory not empty:  (blue)
-/// Directory symlink:  (cyan)
-/// Symlink:  (cyan)
-/// File:  (green)
-fn get_file_icon(entry: DirEntry) -> &'static str {
-    let path = entry.path();
+/// Parse a size with an optional unit suffix into bytes, in binary units
+/// Examples:
+/// "512" -> 512
+/// "10kb" -> 10240
+/// "2.5gb" -> 2684354560
+/// "abc" -> Err("Invalid size: abc, expected a number with an optional unit like 10mb")
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    parse_size_with(s, false)
+}
 
-    let is_dir = path.is_dir();
-    let is_symlink = entry.path_is_symlink();
-    let is_file = path.is_file();
-    let is_nonempty_dir = is_dir
-        && fs::read_dir(path)
-            .map(|mut r| r.next().is_some())
-            .unwrap_or(false);
+/// Parse a size with an optional unit suffix into bytes
+/// If si is true, units are powers of 1000 instead of 1024
+/// Examples:
+/// ("10kb", false) -> 10240
+/// ("10kb", true) -> 10000
+pub fn parse_size_with(s: &str, si: bool) -> Result<u64, String> {
+    Ok(Size::from_str(s)?.bytes(si))
+}
 
-    match (is_symlink, is_dir, is_file, is_nonempty_dir) {
-        (true, _, _, _) => "\x1b[36m\x1b[0m",     // Symlink
-        (_, true, _, true) => "\x1b[34m\x1b[0m",  // Non-empty directory
-        (_, true, _, false) => "\x1b[34m\x1b[0m", // Empty directory
-        (_, _, true, _) => "\x1b[32m\x1b[0m",     // File
-        _ => "",
+/// Parse an absolute date for --modified-after and --modified-before, in local time
+/// A date without a time means the start of that day
+/// Examples:
+/// "2023-01-01" -> 2023-01-01 00:00:00
+/// "2023-01-01 12:30" -> 2023-01-01 12:30:00
+/// "2023-01-01T12:30:45" -> 2023-01-01 12:30:45
+/// "yesterday" -> Err("Invalid date: yesterday, expected YYYY-MM-DD or YYYY-MM-DD HH:MM[:SS]")
+pub fn parse_date(s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    let datetime = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    });
+
+    datetime
+        .and_then(|datetime| Local.from_local_datetime(&datetime).earliest())
+        .map(SystemTime::from)
+        .ok_or_else(|| {
+            format!(
+                "Invalid date: {}, expected YYYY-MM-DD or YYYY-MM-DD HH:MM[:SS]",
+                s
+            )
+        })
+}
+
+/// Parse a time for --since, either an absolute date or how long ago
+/// Relative times are a number with s, m, h, d or w for seconds up to weeks
+/// Examples:
+/// "2023-01-01" -> 2023-01-01 00:00:00
+/// "2h" -> two hours ago
+/// "3d" -> three days ago
+/// "soon" -> Err("Invalid time: soon, expected a date or a duration like 2h or 3d")
+pub fn parse_since(s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    let relative = s
+        .char_indices()
+        .last()
+        .and_then(|(i, unit)| {
+            let seconds = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 3600,
+                'd' => 86400,
+                'w' => 604800,
+                _ => return None,
+            };
+            s[..i].parse::<u64>().ok()?.checked_mul(seconds)
+        })
+        .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)));
+
+    if let Some(time) = relative {
+        return Ok(time);
     }
+
+    parse_date(s).map_err(|_| {
+        format!(
+            "Invalid time: {}, expected a date or a duration like 2h or 3d",
+            s
+        )
+    })
 }
 
-/// Check if a file is hidden (starts with a dot)
+/// Parse a block size for --block-size, it has to be at least one byte
 /// Examples:
-/// .hidden -> true
-/// visible -> false
-fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| s.starts_with('.'))
-        .unwrap_or(false)
+/// "1k" -> 1 kilobyte
+/// "512" -> 512 bytes
+/// "0" -> Err("Invalid block size: 0, expected at least 1 byte")
+pub fn parse_block_size(s: &str) -> Result<Size, String> {
+    let size = Size::from_str(s)?;
+    if size.bytes(false) == 0 {
+        return Err(format!(
+            "Invalid block size: {}, expected at least 1 byte",
+            s
+        ));
+    }
+
+    Ok(size)
 }
 
-/// Get the file creation date as a formatted string
-/// If the creation date cannot be determined, return None
+/// Parse a glob pattern for --glob and --exclude
+/// Wildcards don't cross a "/", so with --match-path a pattern like "src/*.rs"
+/// only matches files directly in src, and "src/**/*.rs" matches them at any depth
 /// Examples:
-/// 2023-10-01 12:34:56 -> "10/01/23 12:34:56"
-/// If creation date is not available -> None
-fn get_file_creation_date(entry: walkdir::DirEntry) -> Option<String> {
-    let metadata = entry.path().metadata().ok()?;
-    let system_time = metadata.created().ok()?;
-    let datetime: DateTime<Local> = system_time.into();
+/// "*.rs" -> Ok
+/// "[a-" -> Err("Invalid glob: [a-, ...")
+pub fn parse_glob(s: &str) -> Result<Glob, String> {
+    GlobBuilder::new(s)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("Invalid glob: {}, {}", s, e))
+}
+
+/// Build the matcher of a glob, ignoring case if asked to
+/// The glob was parsed already, so building it again can't fail
+#[cfg(feature = "cli")]
+fn compile_glob(glob: &Glob, ignore_case: bool) -> GlobMatcher {
+    if !ignore_case {
+        return glob.compile_matcher();
+    }
 
-    Some(datetime.formatl("%x %X", "").to_string())
+    GlobBuilder::new(glob.glob())
+        .literal_separator(true)
+        .case_insensitive(true)
+        .build()
+        .unwrap_or_else(|_| glob.clone())
+        .compile_matcher()
 }
 
-fn get_file_extension(entry: walkdir::DirEntry) -> Option<String> {
-    let ext = entry.path().extension()?.to_str()?;
-    Some(ext.to_string())
+/// Build a regex again so it ignores case, if asked to
+/// Example, with ignore_case:
+/// "README" -> matches "readme"
+#[cfg(feature = "cli")]
+fn compile_regex(regex: Regex, ignore_case: bool) -> Regex {
+    if !ignore_case {
+        return regex;
+    }
+
+    regex::RegexBuilder::new(regex.as_str())
+        .case_insensitive(true)
+        .build()
+        .unwrap_or(regex)
 }
 
-/// Get the file permissions as a rwx string
+/// Keys that entries can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Created,
+    Modified,
+    Accessed,
+    Name,
+    Size,
+    /// Directories before everything else
+    Dir,
+    /// Keep the order the filesystem returns entries in, without reading any metadata
+    None,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+    /// Parse a string into a SortKey enum
+    /// Examples:
+    /// "created" -> SortKey::Created
+    /// "mtime" -> SortKey::Modified
+    /// "dirs" -> SortKey::Dir
+    /// "none" -> SortKey::None
+    /// "invalid" -> Err("Invalid sort key: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created" | "ctime" => Ok(SortKey::Created),
+            "modified" | "mtime" => Ok(SortKey::Modified),
+            "accessed" | "atime" => Ok(SortKey::Accessed),
+            "name" => Ok(SortKey::Name),
+            "size" => Ok(SortKey::Size),
+            "dir" | "dirs" => Ok(SortKey::Dir),
+            "none" => Ok(SortKey::None),
+            _ => Err(format!("Invalid sort key: {}", s)),
+        }
+    }
+}
+
+/// Direction to sort a key in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Direction {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+    /// Parse a string into a Direction enum
+    /// Examples:
+    /// "asc" -> Direction::Asc
+    /// "DESC" -> Direction::Desc
+    /// "up" -> Err("Invalid sort direction: up")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(Direction::Asc),
+            "desc" => Ok(Direction::Desc),
+            _ => Err(format!("Invalid sort direction: {}", s)),
+        }
+    }
+}
+
+impl Direction {
+    /// Get the opposite direction
+    pub fn reversed(&self) -> Direction {
+        match self {
+            Direction::Asc => Direction::Desc,
+            Direction::Desc => Direction::Asc,
+        }
+    }
+
+    /// Flip an ordering when sorting descending
+    pub fn apply(&self, ordering: Ordering) -> Ordering {
+        match self {
+            Direction::Asc => ordering,
+            Direction::Desc => ordering.reverse(),
+        }
+    }
+}
+
+/// A list of sort keys with directions, the first key that differs decides
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortExpr(pub Vec<(SortKey, Direction)>);
+
+impl FromStr for SortExpr {
+    type Err = String;
+    /// Parse a comma separated list of keys, each with an optional direction
+    /// Examples:
+    /// "size desc, name asc" -> [(SortKey::Size, Direction::Desc), (SortKey::Name, Direction::Asc)]
+    /// "modified" -> [(SortKey::Modified, Direction::Asc)]
+    /// "colour desc" -> Err("Invalid sort key: colour")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split(',')
+            .map(|part| {
+                let mut words = part.split_whitespace();
+                let key =
+                    SortKey::from_str(words.next().ok_or("Empty sort key in sort expression")?)?;
+                let direction = words
+                    .next()
+                    .map(Direction::from_str)
+                    .transpose()?
+                    .unwrap_or_default();
+
+                if let Some(extra) = words.next() {
+                    return Err(format!(
+                        "Unexpected {} in sort expression: {}",
+                        extra,
+                        part.trim()
+                    ));
+                }
+
+                Ok((key, direction))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(SortExpr(keys))
+    }
+}
+
+/// Parse one --sort key with an optional direction after a colon
 /// Examples:
-/// rwxr-xr-x -> "rwxr-xr-x"
-fn get_file_permissions(entry: walkdir::DirEntry) -> Option<String> {
-    let metadata = entry.path().metadata().ok()?;
-    let mode = metadata.permissions().mode();
+/// "size" -> (SortKey::Size, Direction::Asc)
+/// "size:desc" -> (SortKey::Size, Direction::Desc)
+/// "size:down" -> Err("Invalid sort direction: down")
+pub fn parse_sort_key(s: &str) -> Result<(SortKey, Direction), String> {
+    let (key, direction) = match s.split_once(':') {
+        Some((key, direction)) => (key, Direction::from_str(direction.trim())?),
+        None => (s, Direction::Asc),
+    };
 
-    // this somehow gets the last 9 bits
-    // idk how because im a retard
-    let perms = mode & 0o777;
+    Ok((SortKey::from_str(key.trim())?, direction))
+}
 
-    let to_rwx = |_, shift: u8| {
-        // this is magic to me
-        let bits = (perms >> shift) & 0o7;
-        format!(
-            "{}{}{}",
-            if bits & 0o4 != 0 { 'r' } else { '-' },
-            if bits & 0o2 != 0 { 'w' } else { '-' },
-            if bits & 0o1 != 0 { 'x' } else { '-' }
+/// Timestamps that can be shown in the date column
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeField {
+    #[default]
+    Created,
+    Modified,
+    Accessed,
+}
+
+impl FromStr for TimeField {
+    type Err = String;
+    /// Parse a string into a TimeField enum
+    /// Examples:
+    /// "created" -> TimeField::Created
+    /// "atime" -> TimeField::Accessed
+    /// "invalid" -> Err("Invalid time field: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created" | "ctime" => Ok(TimeField::Created),
+            "modified" | "mtime" => Ok(TimeField::Modified),
+            "accessed" | "atime" => Ok(TimeField::Accessed),
+            _ => Err(format!("Invalid time field: {}", s)),
+        }
+    }
+}
+
+/// Presets for formatting dates in the date column, like `ls --time-style`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeStyle {
+    /// The locale's date and time, e.g. "05/01/24 12:34:56"
+    #[default]
+    Locale,
+    /// Month, day and time for recent dates and the full date for older ones,
+    /// e.g. "05-01 12:34" or "2023-05-01"
+    Iso,
+    /// e.g. "2024-05-01 12:34"
+    LongIso,
+    /// e.g. "2024-05-01 12:34:56.000000000 +0200"
+    FullIso,
+    /// Time since the date, e.g. "5 minutes ago"
+    Relative,
+}
+
+impl FromStr for TimeStyle {
+    type Err = String;
+    /// Parse a string into a TimeStyle enum
+    /// Examples:
+    /// "long-iso" -> TimeStyle::LongIso
+    /// "relative" -> TimeStyle::Relative
+    /// "invalid" -> Err("Invalid time style: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "locale" => Ok(TimeStyle::Locale),
+            "iso" => Ok(TimeStyle::Iso),
+            "long-iso" => Ok(TimeStyle::LongIso),
+            "full-iso" => Ok(TimeStyle::FullIso),
+            "relative" => Ok(TimeStyle::Relative),
+            _ => Err(format!("Invalid time style: {}", s)),
+        }
+    }
+}
+
+/// Columns of a row and of the table that can be picked and ordered with --columns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Name,
+    Ext,
+    /// The date of a time field, or of --time when None
+    Date(Option<TimeField>),
+    Perms,
+    Size,
+    Scaled,
+    Encoding,
+    Children,
+    Xattrs,
+    Share,
+    Drift,
+    Age,
+    Allocated,
+    Owner,
+    Flags,
+    Hash,
+}
+
+/// Column names --columns accepts, listed when an unknown one is given
+const COLUMN_NAMES: &str = "name, ext, date, created, modified, accessed, perms, size, scaled, \
+                            encoding, children, xattrs, share, drift, age, allocated, owner, \
+                            flags, hash";
+
+impl FromStr for Column {
+    type Err = String;
+    /// Parse a string into a Column enum
+    /// Examples:
+    /// "size" -> Column::Size
+    /// "modified" -> Column::Date(Some(TimeField::Modified))
+    /// "invalid" -> Err("Invalid column: invalid, expected one of name, ext, ...")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Column::Name),
+            "ext" | "extension" => Ok(Column::Ext),
+            "date" => Ok(Column::Date(None)),
+            "created" => Ok(Column::Date(Some(TimeField::Created))),
+            "modified" => Ok(Column::Date(Some(TimeField::Modified))),
+            "accessed" => Ok(Column::Date(Some(TimeField::Accessed))),
+            "perms" | "permissions" => Ok(Column::Perms),
+            "size" => Ok(Column::Size),
+            "scaled" => Ok(Column::Scaled),
+            "encoding" => Ok(Column::Encoding),
+            "children" => Ok(Column::Children),
+            "xattrs" => Ok(Column::Xattrs),
+            "share" | "percent" => Ok(Column::Share),
+            "drift" => Ok(Column::Drift),
+            "age" => Ok(Column::Age),
+            "allocated" | "sparse" => Ok(Column::Allocated),
+            "owner" => Ok(Column::Owner),
+            "flags" => Ok(Column::Flags),
+            "hash" => Ok(Column::Hash),
+            _ => Err(format!(
+                "Invalid column: {}, expected one of {}",
+                s, COLUMN_NAMES
+            )),
+        }
+    }
+}
+
+impl Column {
+    /// Get the title of the column in the table header
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Ext => "Ext",
+            Column::Date(None) => "Date",
+            Column::Date(Some(TimeField::Created)) => "Created",
+            Column::Date(Some(TimeField::Modified)) => "Modified",
+            Column::Date(Some(TimeField::Accessed)) => "Accessed",
+            Column::Perms => "Permissions",
+            Column::Size => "Size",
+            Column::Scaled => "Scaled",
+            Column::Encoding => "Encoding",
+            Column::Children => "Children",
+            Column::Xattrs => "Xattrs",
+            Column::Share => "Share",
+            Column::Drift => "Drift",
+            Column::Age => "Age",
+            Column::Allocated => "Allocated",
+            Column::Owner => "Owner",
+            Column::Flags => "Flags",
+            Column::Hash => "Hash",
+        }
+    }
+
+    /// Check if the cells of the column line up on the right, like numbers
+    fn right_aligned(self) -> bool {
+        matches!(
+            self,
+            Column::Size
+                | Column::Scaled
+                | Column::Children
+                | Column::Share
+                | Column::Drift
+                | Column::Allocated
         )
-    };
+    }
 
-    let owner = to_rwx(perms, 6);
-    let group = to_rwx(perms, 3);
-    let others = to_rwx(perms, 0);
+    /// Get the width of the column in a row
+    fn width(self, config: &Config) -> usize {
+        match self {
+            Column::Name => config.name_width.unwrap_or(26),
+            Column::Ext => EXT_WIDTH,
+            Column::Date(_) => date_column_width(config),
+            Column::Perms => PERM_WIDTH,
+            Column::Size => size_column_width(config),
+            Column::Scaled => scaled_size_width(config),
+            Column::Encoding => ENCODING_WIDTH,
+            Column::Children => CHILD_COUNT_WIDTH,
+            Column::Xattrs => XATTR_WIDTH,
+            Column::Share => PERCENT_WIDTH,
+            Column::Drift => DRIFT_WIDTH,
+            Column::Age => AGE_BUCKET_WIDTH,
+            Column::Allocated => SPARSE_WIDTH,
+            Column::Owner => OWNER_WIDTH,
+            Column::Flags => FLAGS_WIDTH,
+            Column::Hash => HASH_WIDTH,
+        }
+    }
+}
 
-    Some(format!("{}{}{}", owner, group, others))
+/// Columns that can be colored on a gradient with --color-scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleColumn {
+    Size,
+    Date,
 }
 
-/// Get the file size in the specified unit
-/// If the file size cannot be determined, return None
-/// Examples:
-/// 1024 bytes with Unit::KB -> "1 kib"
-/// 1048576 bytes with Unit::MB -> "1 mib"
-fn get_file_size(entry: walkdir::DirEntry, unit: &Unit) -> Option<String> {
-    match entry.path().metadata() {
-        Ok(metadata) => {
-            let size_in_bytes = metadata.len();
-            let size = match unit {
-                Unit::Bytes => size_in_bytes,
-                Unit::KB => size_in_bytes / 1024,
-                Unit::MB => size_in_bytes / (1024 * 1024),
-                Unit::GB => size_in_bytes / (1024 * 1024 * 1024),
-                Unit::TB => size_in_bytes / (1024 * 1024 * 1024 * 1024),
-            };
-            Some(format!("{} {}", size, normalize_size_unit(unit)))
+impl FromStr for ScaleColumn {
+    type Err = String;
+    /// Parse a string into a ScaleColumn enum
+    /// Examples:
+    /// "size" -> ScaleColumn::Size
+    /// "date" -> ScaleColumn::Date
+    /// "invalid" -> Err("Invalid scale column: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "size" => Ok(ScaleColumn::Size),
+            "date" | "time" => Ok(ScaleColumn::Date),
+            _ => Err(format!("Invalid scale column: {}", s)),
         }
-        Err(_) => None,
     }
 }
 
-/// Render a single file entry as a formatted row
-fn render_as_row(entry: walkdir::DirEntry, canonicalize: bool, unit: &Unit) -> String {
-    let name = get_file_name(entry.clone(), canonicalize).map_err(|e| {
-        eprintln!("{}", e);
-        std::process::exit(1);
-    });
+/// The smallest and largest values in a listing, the ends of the --color-scale gradient
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScaleRange {
+    pub size: Option<(u64, u64)>,
+    pub time: Option<(SystemTime, SystemTime)>,
+}
 
-    let ext = get_file_extension(entry.clone()).unwrap_or_else(|| "-".to_string());
+impl ScaleRange {
+    /// Find the range of sizes and of the date column times in the entries
+    pub fn new(entries: &[FileEntry], config: &Config) -> Self {
+        let sizes = entries.iter().filter_map(|entry| entry.size);
+        let times = entries
+            .iter()
+            .filter_map(|entry| get_file_time(entry, config.time.unwrap_or_default(), config));
 
-    let creation_date = get_file_creation_date(entry.clone()).unwrap_or_else(|| "-".to_string());
+        ScaleRange {
+            size: sizes.clone().min().zip(sizes.max()),
+            time: times.clone().min().zip(times.max()),
+        }
+    }
+}
 
-    let permissions = get_file_permissions(entry.clone()).unwrap_or_else(|| "-".to_string());
+/// Order of the rows in the extension summary, largest first
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ExtensionSort {
+    #[default]
+    Count,
+    Size,
+}
 
-    let size = get_file_size(entry.clone(), unit)
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "-".to_string());
+impl FromStr for ExtensionSort {
+    type Err = String;
+    /// Parse a string into an ExtensionSort enum
+    /// Examples:
+    /// "count" -> ExtensionSort::Count
+    /// "size" -> ExtensionSort::Size
+    /// "invalid" -> Err("Invalid extension sort: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "count" => Ok(ExtensionSort::Count),
+            "size" => Ok(ExtensionSort::Size),
+            _ => Err(format!("Invalid extension sort: {}", s)),
+        }
+    }
+}
 
-    let name_width = 35;
-    let date_width = 20;
-    let perm_width = 12;
-    let size_width = 10;
-    let ext_width = 5;
+/// When to use colors in the output
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorMode {
+    /// Color only when writing to a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
 
-    let name = name.ok().map(|n| n.to_string());
-    let name = name.as_deref().unwrap_or("-");
-    let is_dir = entry.path().is_dir();
-    let ext = format!("{}{}", if is_dir { "" } else { "." }, ext);
+impl FromStr for ColorMode {
+    type Err = String;
+    /// Parse a string into a ColorMode enum
+    /// Examples:
+    /// "auto" -> ColorMode::Auto
+    /// "always" -> ColorMode::Always
+    /// "invalid" -> Err("Invalid color mode: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("Invalid color mode: {}", s)),
+        }
+    }
+}
 
-    format!(
-        "{:<name_width$} {:<ext_width$} {:<date_width$} {:<perm_width$} {:>size_width$}",
-        &name.truncate_ellipsis(name_width - 1).bold(),
-        &ext,
-        &creation_date.truncate_ellipsis(date_width - 1),
-        &permissions.truncate_ellipsis(perm_width - 1),
-        size
-    )
+impl ColorMode {
+    /// Make the colored crate follow this mode
+    /// Auto leaves the decision to colored, which checks for a terminal and NO_COLOR
+    pub fn apply(&self) {
+        match self {
+            ColorMode::Auto => colored::control::unset_override(),
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+/// Output formats that we support
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    /// One JSON object per line, flushed as soon as it is written
+    Ndjson,
+    Csv,
+    /// Text with box drawing borders and a header row
+    Table,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    /// Parse a string into a Format enum
+    /// Examples:
+    /// "text" -> Format::Text
+    /// "JSON" -> Format::Json
+    /// "invalid" -> Err("Invalid format: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "ndjson" | "jsonl" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            "table" => Ok(Format::Table),
+            _ => Err(format!("Invalid format: {}", s)),
+        }
+    }
+}
+
+/// Compressors the --output file can be written through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+    /// Parse a string into a Compression enum
+    /// Examples:
+    /// "gz" -> Compression::Gzip
+    /// "zstd" -> Compression::Zstd
+    /// "invalid" -> Err("Invalid compression: invalid")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gz" | "gzip" => Ok(Compression::Gzip),
+            "zst" | "zstd" => Ok(Compression::Zstd),
+            _ => Err(format!("Invalid compression: {}", s)),
+        }
+    }
+}
+
+impl Compression {
+    /// Pick the compressor from the extension of an output file
+    /// Examples:
+    /// "snapshot.json.gz" -> Some(Compression::Gzip)
+    /// "snapshot.csv.zst" -> Some(Compression::Zstd)
+    /// "snapshot.json" -> None
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+/// The type of a file entry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    /// Unix domain socket
+    Socket,
+    /// Named pipe
+    Fifo,
+    BlockDevice,
+    CharDevice,
+    Other,
+    /// A file inside an archive, it doesn't exist on disk
+    ArchiveMember,
+}
+
+/// Get the kind of a file that is not a regular file, directory or symlink
+/// Examples:
+/// /dev/sda -> BlockDevice
+/// /dev/null -> CharDevice
+fn get_special_kind(file_type: fs::FileType) -> EntryKind {
+    if file_type.is_socket() {
+        EntryKind::Socket
+    } else if file_type.is_fifo() {
+        EntryKind::Fifo
+    } else if file_type.is_block_device() {
+        EntryKind::BlockDevice
+    } else if file_type.is_char_device() {
+        EntryKind::CharDevice
+    } else {
+        EntryKind::Other
+    }
+}
+
+/// Everything fview knows about a single file
+/// This is the data side of a listing, the render functions only format it
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    /// File name without any directories
+    pub name: String,
+    /// Path as walked, relative if the listed directory was relative
+    pub path: PathBuf,
+    /// Depth below the listed directory, starting at 1
+    pub depth: usize,
+    pub kind: EntryKind,
+    /// Size in bytes, or the total size of the contents for directories with dir_size on
+    pub size: Option<u64>,
+    /// True when the directory total is a lower bound, because size_depth cut it
+    /// short or part of the tree couldn't be read
+    pub size_partial: bool,
+    /// Permissions as a rwx string
+    pub permissions: Option<String>,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    /// When the entry was moved to the trash, only set for trash listings
+    pub deleted: Option<SystemTime>,
+    /// SHA-256 of the contents, only set for files when hashing is on
+    pub hash: Option<String>,
+    /// Share of the parent directory's total size in percent, only set with --percent
+    pub percent: Option<f64>,
+    /// Bytes allocated on disk, only set for files
+    pub allocated: Option<u64>,
+    /// True when the entry doesn't match the patterns and is only listed as
+    /// context around a match, with --context
+    pub context: bool,
+    /// True for a symlink whose target couldn't be read with --deref, so the
+    /// link's own metadata is shown instead
+    pub broken_link: bool,
+}
+
+impl FileEntry {
+    /// Build a file entry from a walked directory entry, reading its metadata
+    /// Symlinks show the link's own metadata, or the target's with deref
+    /// With no_metadata nothing is read, the type comes from the directory listing
+    pub fn from_dir_entry(entry: &DirEntry, config: &Config) -> Self {
+        let path = entry.path();
+
+        if config.no_metadata {
+            return FileEntry::from_file_type(entry);
+        }
+        let (metadata, broken_link) = read_entry_metadata(path, config.deref);
+
+        let kind = if entry.path_is_symlink() {
+            EntryKind::Symlink
+        } else if path.is_dir() {
+            EntryKind::Dir
+        } else if path.is_file() {
+            EntryKind::File
+        } else {
+            get_special_kind(entry.file_type())
+        };
+        let (size, size_partial) = get_file_size_bytes(entry, metadata.as_ref(), config);
+
+        FileEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            path: path.to_path_buf(),
+            depth: entry.depth(),
+            kind,
+            size,
+            size_partial,
+            permissions: get_file_permissions(metadata.as_ref()),
+            created: metadata.as_ref().and_then(|m| m.created().ok()),
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            accessed: metadata.as_ref().and_then(|m| m.accessed().ok()),
+            deleted: None,
+            hash: None,
+            percent: None,
+            allocated: get_allocated_size(metadata.as_ref()),
+            context: false,
+            broken_link,
+        }
+    }
+
+    /// Build an entry with only a name and a type, from the file type the directory
+    /// listing already has, without any further system calls
+    fn from_file_type(entry: &DirEntry) -> Self {
+        let file_type = entry.file_type();
+        let kind = if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else {
+            get_special_kind(file_type)
+        };
+
+        FileEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            path: entry.path().to_path_buf(),
+            depth: entry.depth(),
+            kind,
+            size: None,
+            size_partial: false,
+            permissions: None,
+            created: None,
+            modified: None,
+            accessed: None,
+            deleted: None,
+            hash: None,
+            percent: None,
+            allocated: None,
+            context: false,
+            broken_link: false,
+        }
+    }
+
+    /// Build a virtual entry for a file inside an archive entry
+    /// Archive members have a size but no permissions or timestamps
+    pub fn from_archive_member(archive: &FileEntry, member: ArchiveMember) -> Self {
+        FileEntry {
+            path: archive.path.join(&member.name),
+            name: member.name,
+            depth: archive.depth + 1,
+            kind: EntryKind::ArchiveMember,
+            size: Some(member.size),
+            size_partial: false,
+            permissions: None,
+            created: None,
+            modified: None,
+            accessed: None,
+            deleted: None,
+            hash: None,
+            percent: None,
+            allocated: None,
+            context: false,
+            broken_link: false,
+        }
+    }
+
+    /// Build an entry for a file in the trash
+    /// The name is the original path, metadata is read from the file inside the trash
+    pub fn from_trash_item(item: TrashItem, config: &Config) -> Self {
+        FileEntry {
+            name: item.original_path.to_string_lossy().into_owned(),
+            deleted: item.deleted,
+            ..FileEntry::from_path(item.path, config)
+        }
+    }
+
+    /// Build an entry for a path that wasn't found by walking, named by the path itself
+    /// Symlinks are not followed, so a link is listed as a link, with the link's
+    /// own metadata or the target's with deref
+    pub fn from_path(path: PathBuf, config: &Config) -> Self {
+        let kind = match path.symlink_metadata() {
+            Ok(m) if m.file_type().is_symlink() => EntryKind::Symlink,
+            Ok(m) if m.is_dir() => EntryKind::Dir,
+            Ok(m) if m.is_file() => EntryKind::File,
+            Ok(m) => get_special_kind(m.file_type()),
+            Err(_) => EntryKind::Other,
+        };
+        let (metadata, broken_link) = read_entry_metadata(&path, config.deref);
+
+        let (size, size_partial) = if config.dir_size && kind == EntryKind::Dir {
+            let size = dir_size(
+                &path,
+                config.verbose,
+                config.size_depth,
+                config.max_total_size,
+            );
+            (Some(size.bytes), size.is_partial())
+        } else {
+            (metadata.as_ref().map(|m| m.len()), false)
+        };
+
+        FileEntry {
+            name: path.to_string_lossy().into_owned(),
+            permissions: get_file_permissions(metadata.as_ref()),
+            path,
+            depth: 1,
+            kind,
+            size,
+            size_partial,
+            created: metadata.as_ref().and_then(|m| m.created().ok()),
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            accessed: metadata.as_ref().and_then(|m| m.accessed().ok()),
+            deleted: None,
+            hash: None,
+            percent: None,
+            allocated: get_allocated_size(metadata.as_ref()),
+            context: false,
+            broken_link,
+        }
+    }
+
+    /// Get the file extension, if there is one
+    pub fn extension(&self) -> Option<String> {
+        get_file_extension(&self.path)
+    }
+}
+
+/// Read the metadata shown for an entry, the link's own for symlinks unless deref
+/// is on, then the target's. The second value is true for a broken link with deref,
+/// which falls back to the link's own metadata
+fn read_entry_metadata(path: &Path, deref: bool) -> (Option<fs::Metadata>, bool) {
+    if deref && let Ok(metadata) = path.metadata() {
+        return (Some(metadata), false);
+    }
+
+    let metadata = path.symlink_metadata().ok();
+    let broken_link = deref
+        && metadata
+            .as_ref()
+            .is_some_and(|m| m.file_type().is_symlink());
+    (metadata, broken_link)
+}
+
+/// Get the number of bytes a file takes up on disk, in 512 byte blocks like `du`
+/// Only files get one, a directory's blocks say nothing about its contents
+fn get_allocated_size(metadata: Option<&fs::Metadata>) -> Option<u64> {
+    metadata.filter(|m| m.is_file()).map(|m| m.blocks() * 512)
+}
+
+/// Check if a file is sparse, taking up much less room on disk than its size
+/// That is less than half its size, with at least a page worth of holes, so
+/// small files that fit in their inode don't count
+/// Examples:
+/// 10 gib file with 1 mib allocated -> true
+/// 100 byte file with nothing allocated -> false
+fn is_sparse(entry: &FileEntry) -> bool {
+    match (entry.size, entry.allocated) {
+        (Some(size), Some(allocated)) => {
+            size.saturating_sub(allocated) >= 4096 && allocated < size / 2
+        }
+        _ => false,
+    }
+}
+
+/// A file entry in a machine readable shape, used by the JSON and CSV formats
+/// Both the raw byte count and the formatted size are included so consumers
+/// don't have to redo the unit scaling themselves
+#[derive(Debug, Serialize)]
+pub struct EntryRecord {
+    pub name: String,
+    pub path: String,
+    pub extension: Option<String>,
+    /// Timestamps are RFC 3339 in JSON and in the locale format in CSV
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+    pub permissions: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub size_human: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl EntryRecord {
+    fn new(entry: &FileEntry, config: &Config) -> Self {
+        let unit = config.unit.clone().unwrap_or_default();
+        let format_time = |time: Option<SystemTime>| match config.format {
+            Format::Json | Format::Ndjson => time.map(format_rfc3339),
+            _ => time.map(format_system_time),
+        };
+
+        EntryRecord {
+            name: get_plain_file_name(entry, config),
+            path: entry.path.to_string_lossy().into_owned(),
+            extension: entry.extension(),
+            created: format_time(entry.created),
+            modified: format_time(entry.modified),
+            accessed: format_time(entry.accessed),
+            permissions: entry.permissions.clone(),
+            size_bytes: entry.size,
+            size_human: get_file_size(entry, &unit, config),
+            sha256: entry.hash.clone(),
+        }
+    }
+}
+
+/// Aggregate stats over a whole listing, for --summary-json
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub total_files: usize,
+    pub total_dirs: usize,
+    /// Sum of the file sizes, directories don't count
+    pub total_bytes: u64,
+    pub largest_file: Option<SummaryFile>,
+    /// The most recently modified file
+    pub newest_file: Option<SummaryFile>,
+    /// File count and bytes per extension, files without one are under ""
+    pub extensions: BTreeMap<String, ExtensionStats>,
+}
+
+/// A single file picked out by the summary
+#[derive(Debug, Serialize)]
+pub struct SummaryFile {
+    pub path: String,
+    pub size_bytes: Option<u64>,
+    pub modified: Option<String>,
+}
+
+/// Totals for one extension in the summary
+#[derive(Debug, Default, Serialize)]
+pub struct ExtensionStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+impl Summary {
+    /// Sum up the collected entries
+    pub fn new(entries: &[FileEntry]) -> Self {
+        let mut summary = Summary::default();
+        let files: Vec<&FileEntry> = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::File)
+            .collect();
+
+        summary.total_files = files.len();
+        summary.total_dirs = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Dir)
+            .count();
+
+        summary.total_bytes = files.iter().map(|file| file.size.unwrap_or(0)).sum();
+        summary.extensions = get_extension_stats(entries);
+
+        let summary_file = |entry: &FileEntry| SummaryFile {
+            path: entry.path.to_string_lossy().into_owned(),
+            size_bytes: entry.size,
+            modified: entry.modified.map(format_rfc3339),
+        };
+        summary.largest_file = files
+            .iter()
+            .max_by_key(|file| file.size)
+            .map(|file| summary_file(file));
+        summary.newest_file = files
+            .iter()
+            .max_by_key(|file| file.modified)
+            .map(|file| summary_file(file));
+
+        summary
+    }
+}
+
+/// Count the files and their bytes per extension
+/// Files without an extension are counted under ""
+fn get_extension_stats(entries: &[FileEntry]) -> BTreeMap<String, ExtensionStats> {
+    let mut extensions: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+
+    for file in entries.iter().filter(|entry| entry.kind == EntryKind::File) {
+        let stats = extensions
+            .entry(file.extension().unwrap_or_default())
+            .or_default();
+        stats.count += 1;
+        stats.bytes += file.size.unwrap_or(0);
+    }
+
+    extensions
+}
+
+/// Normalize the unit to a short string representation
+/// Examples:
+/// Unit::Bytes -> "b"
+/// Unit::KB -> "kib"
+pub fn normalize_size_unit(unit: &Unit) -> &str {
+    match unit {
+        Unit::Bytes => "b",
+        Unit::KB => "kib",
+        Unit::MB => "mib",
+        Unit::GB => "gib",
+        Unit::TB => "tib",
+        Unit::Auto => "auto",
+    }
+}
+
+/// Short SI representation of a unit, for powers of 1000
+/// Examples:
+/// Unit::Bytes -> "b"
+/// Unit::KB -> "kb"
+pub fn si_size_unit(unit: &Unit) -> &str {
+    match unit {
+        Unit::Bytes => "b",
+        Unit::KB => "kb",
+        Unit::MB => "mb",
+        Unit::GB => "gb",
+        Unit::TB => "tb",
+        Unit::Auto => "auto",
+    }
+}
+
+/// Get the spelled out name of a unit in the given locale, plural aware
+/// Unknown locales fall back to English
+/// Examples:
+/// (Unit::Bytes, 1, "") -> "byte"
+/// (Unit::KB, 2, "") -> "kilobytes"
+/// (Unit::MB, 2, "fr_FR") -> "mégaoctets"
+pub fn long_size_unit(unit: &Unit, count: u64, locale: &str) -> String {
+    let language = locale.get(..2).unwrap_or_default();
+
+    let (singular, plural) = match (language, unit) {
+        ("de", Unit::Bytes) => ("Byte", "Byte"),
+        ("de", Unit::KB) => ("Kilobyte", "Kilobyte"),
+        ("de", Unit::MB) => ("Megabyte", "Megabyte"),
+        ("de", Unit::GB) => ("Gigabyte", "Gigabyte"),
+        ("de", Unit::TB) => ("Terabyte", "Terabyte"),
+        ("fr", Unit::Bytes) => ("octet", "octets"),
+        ("fr", Unit::KB) => ("kilooctet", "kilooctets"),
+        ("fr", Unit::MB) => ("mégaoctet", "mégaoctets"),
+        ("fr", Unit::GB) => ("gigaoctet", "gigaoctets"),
+        ("fr", Unit::TB) => ("téraoctet", "téraoctets"),
+        (_, Unit::Bytes) => ("byte", "bytes"),
+        (_, Unit::KB) => ("kilobyte", "kilobytes"),
+        (_, Unit::MB) => ("megabyte", "megabytes"),
+        (_, Unit::GB) => ("gigabyte", "gigabytes"),
+        (_, Unit::TB) => ("terabyte", "terabytes"),
+        (_, Unit::Auto) => ("auto", "auto"),
+    };
+
+    if count == 1 { singular } else { plural }.to_string()
+}
+
+/// Walk the configured directory and collect its entries
+/// Filtering and sorting follow the configuration, entries that can't be read
+/// are reported on stderr and skipped
+pub fn collect_entries(config: &Config) -> Vec<FileEntry> {
+    let mut collected = if let Some(paths) = &config.paths {
+        collect_path_entries(paths, config)
+    } else if config.trash {
+        collect_trash_entries(config)
+    } else {
+        let depth = config.max_depth.unwrap_or(1);
+
+        let root = normalize_start_dir(&config.dir);
+        let mut followed = root.canonicalize().into_iter().collect();
+        let mut collected = Vec::new();
+
+        walk_dir(&root, 0, depth, config, &mut followed, &mut collected);
+        collected
+    };
+
+    if let Some(changed) = &config.changed_paths {
+        collected.retain(|entry| is_changed_path(entry, changed));
+    }
+
+    if config.leaves_only {
+        collected.retain(is_leaf);
+    }
+
+    if config.empty_trees {
+        retain_empty_trees(&mut collected);
+    }
+
+    if config.skip_symlinks {
+        collected.retain(|entry| entry.kind != EntryKind::Symlink);
+    }
+
+    if let Some(query) = &config.fuzzy {
+        retain_fuzzy_matches(&mut collected, query, config);
+    }
+
+    if config.flatten && config.paths.is_none() && !config.trash {
+        flatten_entries(&mut collected, config);
+    }
+
+    if config.duplicates {
+        // only files sharing their size with another file can have the same contents
+        let mut sizes: HashMap<u64, usize> = HashMap::new();
+        for entry in collected.iter().filter(|e| e.kind == EntryKind::File) {
+            *sizes.entry(entry.size.unwrap_or(0)).or_default() += 1;
+        }
+        collected.retain(|e| e.kind == EntryKind::File && sizes[&e.size.unwrap_or(0)] > 1);
+    }
+
+    if config.hash || config.duplicates {
+        hash_entries(&mut collected, config);
+    }
+
+    if config.percent {
+        set_percentages(&mut collected, config);
+    }
+
+    if config.duplicates {
+        retain_duplicates(&mut collected);
+    } else if config.depth_sort {
+        sort_by_depth(&mut collected, config.reversed);
+    } else if config.breadth_first {
+        sort_breadth_first(&mut collected, &get_sort_keys(config), config.stable);
+    }
+
+    if let Some(context) = config.context {
+        retain_context(&mut collected, context);
+    }
+
+    collected
+}
+
+/// Keep the matches and the `context` entries right before and after each
+/// match in the listing, like `grep -C`. Everything further away is dropped
+/// Example, with a context of 1:
+/// [a, b, match, c, d] -> [b, match, c]
+fn retain_context(entries: &mut Vec<FileEntry>, context: usize) {
+    let mut keep = vec![false; entries.len()];
+
+    for (index, _) in entries.iter().enumerate().filter(|(_, e)| !e.context) {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(entries.len());
+        keep[start..end].fill(true);
+    }
+
+    let mut keep = keep.into_iter();
+    entries.retain(|_| keep.next().unwrap_or(false));
+}
+
+/// Paths --exec runs its command on, the listed entries that exist on disk and matched
+/// Archive members can't be passed to a command, and context rows are only shown
+/// around the matches, so neither of them is run on
+pub fn exec_paths(entries: &[FileEntry]) -> Vec<PathBuf> {
+    entries
+        .iter()
+        .filter(|entry| entry.kind != EntryKind::ArchiveMember && !entry.context)
+        .map(|entry| entry.path.clone())
+        .collect()
+}
+
+/// Check if an entry has no children, every file is a leaf and so is an empty directory
+/// Hidden entries count as children, even when they aren't shown
+fn is_leaf(entry: &FileEntry) -> bool {
+    entry.kind != EntryKind::Dir || get_child_count(&entry.path, true) == Some(0)
+}
+
+/// Keep only the entries whose name fuzzy matches the query, like `fzf`
+/// Without a sort key on the command line the best matches come first,
+/// entries with the same score keep their order
+fn retain_fuzzy_matches(entries: &mut Vec<FileEntry>, query: &str, config: &Config) {
+    let start = normalize_start_dir(&config.dir);
+    let mut scored: Vec<(i64, FileEntry)> = std::mem::take(entries)
+        .into_iter()
+        .filter_map(|entry| {
+            let input = get_match_input(&entry.path, &start, config);
+            let score = fuzzy_matcher(config.ignore_case).fuzzy_match(&input, query)?;
+            Some((score, entry))
+        })
+        .collect();
+
+    if config.sort.is_none() && config.sort_expr.is_none() {
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+    }
+
+    entries.extend(scored.into_iter().map(|(_, entry)| entry));
+}
+
+/// The matcher for --fuzzy, made once and shared
+/// It uses smart case, a query with capitals has to match them, unless ignore_case
+fn fuzzy_matcher(ignore_case: bool) -> &'static SkimMatcherV2 {
+    static SMART_CASE: OnceLock<SkimMatcherV2> = OnceLock::new();
+    static IGNORE_CASE: OnceLock<SkimMatcherV2> = OnceLock::new();
+
+    match ignore_case {
+        true => IGNORE_CASE.get_or_init(|| SkimMatcherV2::default().ignore_case()),
+        false => SMART_CASE.get_or_init(SkimMatcherV2::default),
+    }
+}
+
+/// Keep only the directories without any files below them, at any depth
+/// A tree inside another one is dropped, so every tree is listed by its top directory
+fn retain_empty_trees(entries: &mut Vec<FileEntry>) {
+    entries.retain(|entry| entry.kind == EntryKind::Dir && is_empty_tree(&entry.path));
+
+    let tops: HashSet<PathBuf> = entries.iter().map(|entry| entry.path.clone()).collect();
+    entries.retain(|entry| {
+        !entry
+            .path
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| tops.contains(ancestor))
+    });
+}
+
+/// Check if a directory holds nothing but empty directories, all the way down
+/// Symlinks count as content and aren't followed. A directory that can't be
+/// read might hold files, so it never counts as empty
+fn is_empty_tree(path: &Path) -> bool {
+    let Ok(read_dir) = fs::read_dir(path) else {
+        return false;
+    };
+
+    read_dir.into_iter().all(|child| {
+        child.is_ok_and(|child| {
+            child.file_type().is_ok_and(|kind| kind.is_dir()) && is_empty_tree(&child.path())
+        })
+    })
+}
+
+/// Hash the contents of every file entry in parallel
+/// Other entries, and files that can't be read, are left without a hash
+fn hash_entries(entries: &mut [FileEntry], config: &Config) {
+    let paths: Vec<PathBuf> = entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::File)
+        .map(|entry| entry.path.clone())
+        .collect();
+
+    let jobs = config.jobs.unwrap_or_else(hash::default_jobs);
+    let mut hashes = hash::hash_files(&paths, jobs);
+
+    for entry in entries {
+        entry.hash = hashes.remove(&entry.path);
+
+        // unreadable files are left without a hash, so they never count as duplicates
+        if config.verbose && entry.kind == EntryKind::File && entry.hash.is_none() {
+            log::warn(format!("Cannot hash file: {}", entry.path.display()));
+        }
+    }
+}
+
+/// Drop the directories and name every other entry by its path relative to the
+/// listed directory, like `find -type f` but with the usual columns
+/// Example:
+/// ./src/config.rs -> "src/config.rs"
+fn flatten_entries(entries: &mut Vec<FileEntry>, config: &Config) {
+    let root = normalize_start_dir(&config.dir);
+    entries.retain(|entry| entry.kind != EntryKind::Dir);
+
+    for entry in entries {
+        if let Ok(relative) = entry.path.strip_prefix(&root) {
+            entry.name = relative.to_string_lossy().into_owned();
+        }
+    }
+}
+
+/// Work out what share of its parent directory's total size every entry takes
+/// Directories count with the total size of their contents, every directory
+/// total is computed once and shared between its entries
+fn set_percentages(entries: &mut [FileEntry], config: &Config) {
+    let mut totals: HashMap<PathBuf, u64> = HashMap::new();
+    let mut dir_total = |path: &Path| {
+        *totals.entry(path.to_path_buf()).or_insert_with(|| {
+            dir_size(
+                path,
+                config.verbose,
+                config.size_depth,
+                config.max_total_size,
+            )
+            .bytes
+        })
+    };
+
+    for entry in entries {
+        if entry.kind == EntryKind::ArchiveMember {
+            continue;
+        }
+
+        let size = match entry.kind {
+            EntryKind::Dir => dir_total(&entry.path),
+            _ => entry.size.unwrap_or(0),
+        };
+        let parent_total = dir_total(&get_parent_dir(entry));
+
+        entry.percent = Some(match parent_total {
+            0 => 0.0,
+            total => size as f64 / total as f64 * 100.0,
+        });
+    }
+}
+
+/// Keep only files whose contents match at least one other file
+/// Copies end up next to each other, ordered by hash and then by path
+fn retain_duplicates(entries: &mut Vec<FileEntry>) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for hash in entries.iter().filter_map(|entry| entry.hash.clone()) {
+        *counts.entry(hash).or_default() += 1;
+    }
+
+    entries.retain(|entry| entry.hash.as_ref().is_some_and(|hash| counts[hash] > 1));
+    entries.sort_by(|a, b| a.hash.cmp(&b.hash).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// Collect the files in the trash instead of walking a directory
+/// Hidden and size filters still apply, a trash that can't be read is
+/// reported on stderr and listed as empty
+fn collect_trash_entries(config: &Config) -> Vec<FileEntry> {
+    let items = match trash::list_trash() {
+        Ok(items) => items,
+        Err(e) => {
+            log::warn(e);
+            return Vec::new();
+        }
+    };
+
+    let mut entries: Vec<FileEntry> = items
+        .into_iter()
+        .map(|item| FileEntry::from_trash_item(item, config))
+        .filter(|entry| config.show_hidden || !is_hidden_path(Path::new(&entry.name)))
+        .filter(|entry| is_within_size_range(entry, config))
+        .collect();
+
+    // sorted like any other listing, ties keep the trash order of oldest deletion first
+    let keys = get_sort_keys(config);
+    if keys.iter().any(|(key, _)| *key != SortKey::None) {
+        entries.sort_by(|a, b| compare_file_entries(a, b, &keys));
+    }
+
+    entries
+}
+
+/// Collect entries for a list of paths instead of walking a directory, e.g. from stdin
+/// Hidden and size filters still apply, paths that don't exist are reported on stderr
+fn collect_path_entries(paths: &[PathBuf], config: &Config) -> Vec<FileEntry> {
+    let mut entries: Vec<FileEntry> = paths
+        .iter()
+        .filter(|path| {
+            let exists = path.symlink_metadata().is_ok();
+            if !exists {
+                log::warn(format!("No such file: {}", path.display()));
+            }
+            exists
+        })
+        .map(|path| FileEntry::from_path(path.clone(), config))
+        .filter(|entry| config.show_hidden || !is_hidden_path(&entry.path))
+        .filter(|entry| is_within_size_range(entry, config))
+        .collect();
+
+    let keys = get_sort_keys(config);
+    if keys.iter().any(|(key, _)| *key != SortKey::None) {
+        entries.sort_by(|a, b| compare_file_entries(a, b, &keys));
+    }
+
+    entries
+}
+
+/// Read the paths to list from stdin, one per line or separated by NUL bytes
+/// Empty paths are skipped
+/// Examples:
+/// "src/main.rs\nCargo.toml\n" -> ["src/main.rs", "Cargo.toml"]
+/// "odd\nname\0Cargo.toml\0" with null -> ["odd\nname", "Cargo.toml"]
+pub fn read_stdin_paths(input: impl io::Read, null: bool) -> io::Result<Vec<PathBuf>> {
+    let separator = if null { b'\0' } else { b'\n' };
+
+    io::BufReader::new(input)
+        .split(separator)
+        .filter(|path| path.as_ref().map_or(true, |path| !path.is_empty()))
+        .map(|path| path.map(|path| PathBuf::from(OsString::from_vec(path))))
+        .collect()
+}
+
+/// Clean up the directory to start walking from
+/// Redundant slashes and `.` components are dropped, and a symlink to a
+/// directory is resolved to its target so its contents get listed
+/// Examples:
+/// "src///" -> "src"
+/// "./src/./config" -> "./src/config"
+/// "mylink/" -> "/path/to/target"
+pub fn normalize_start_dir(dir: &str) -> PathBuf {
+    let path = Path::new(dir);
+    let normalized: PathBuf = path.components().collect();
+    if normalized.as_os_str().is_empty() {
+        return path.to_path_buf();
+    }
+
+    // a trailing slash makes the link resolve already, so check the cleaned up path
+    let is_symlink = normalized
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if is_symlink
+        && normalized.is_dir()
+        && let Ok(target) = normalized.canonicalize()
+    {
+        return target;
+    }
+
+    normalized
+}
+
+/// Walk a directory and push its entries onto `collected`
+/// `depth_offset` is the depth of `root` below the listed directory, it is
+/// only non zero when walking a followed directory symlink
+/// `followed` holds the real paths of the directories we are currently inside,
+/// so a directory symlink pointing back into one of them is not followed again
+fn walk_dir(
+    root: &Path,
+    depth_offset: usize,
+    max_depth: usize,
+    config: &Config,
+    followed: &mut Vec<PathBuf>,
+    collected: &mut Vec<FileEntry>,
+) {
+    let keys = get_sort_keys(config);
+
+    let mut walker = WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(max_depth - depth_offset);
+
+    // without a sort key the comparator, and the metadata reads it needs, are skipped entirely
+    if keys.iter().any(|(key, _)| *key != SortKey::None) {
+        walker = walker.sort_by(move |a, b| compare_dir_entries(a, b, &keys));
+    }
+
+    let start = normalize_start_dir(&config.dir);
+    let show_hidden = config.show_hidden || config.no_recurse_hidden_dirs;
+    let mut entries = walker.into_iter().filter_entry(|e| {
+        (show_hidden || !is_hidden(e))
+            && !exceeds_size_limit(e, config.skip_larger_than)
+            && !is_excluded(e.path(), &start, config)
+            && (!config.exclude_vcs || !is_vcs_dir(e))
+    });
+
+    while let Some(entry) = entries.next() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                let reason = e.source().map(|c| c.to_string()).unwrap_or_default();
+                match e.path() {
+                    Some(path) => log::warn(format!("Cannot read {}: {}", path.display(), reason)),
+                    None => log::warn(reason),
+                }
+                continue;
+            }
+        };
+
+        // hidden directories are listed, but what's inside them is not
+        if config.no_recurse_hidden_dirs && entry.file_type().is_dir() && is_hidden(&entry) {
+            entries.skip_current_dir();
+        }
+
+        let mut file_entry = FileEntry::from_dir_entry(&entry, config);
+        file_entry.depth += depth_offset;
+
+        if !is_within_size_range(&file_entry, config)
+            || !is_within_date_range(&file_entry, config)
+            || !is_owned_by(&file_entry, config)
+        {
+            continue;
+        }
+
+        let is_archive =
+            file_entry.kind == EntryKind::File && archive::is_archive(&file_entry.path);
+        let members = if config.into_archives && is_archive {
+            list_archive_members(&file_entry)
+        } else {
+            Vec::new()
+        };
+
+        let follow = config.follow_only_dirs
+            && file_entry.kind == EntryKind::Symlink
+            && file_entry.path.is_dir()
+            && file_entry.depth < max_depth;
+        let link = follow.then(|| (file_entry.path.clone(), file_entry.depth));
+
+        // entries that don't match are left out, but a followed link is still walked
+        // with context they are kept for now, and trimmed once the listing is complete
+        let matched = matches_patterns(&file_entry.path, &start, config);
+        if (matched || config.context.is_some()) && has_allowed_extension(&file_entry, config) {
+            let context = !matched;
+            collected.push(FileEntry {
+                context,
+                ..file_entry
+            });
+            collected.extend(
+                members
+                    .into_iter()
+                    .map(|member| FileEntry { context, ..member }),
+            );
+        }
+
+        if let Some((path, depth)) = link {
+            follow_dir_symlink(&path, depth, max_depth, config, followed, collected);
+        }
+    }
+}
+
+/// Walk the target of a directory symlink as if it were a normal directory
+/// Links that point to a directory we are already inside are skipped, as
+/// following them would loop until the maximum depth
+fn follow_dir_symlink(
+    link: &Path,
+    depth: usize,
+    max_depth: usize,
+    config: &Config,
+    followed: &mut Vec<PathBuf>,
+    collected: &mut Vec<FileEntry>,
+) {
+    let (Ok(target), Some(Ok(parent))) = (
+        link.canonicalize(),
+        link.parent().map(|parent| parent.canonicalize()),
+    ) else {
+        return;
+    };
+
+    let is_cycle =
+        parent.starts_with(&target) || followed.iter().any(|dir| dir.starts_with(&target));
+    if is_cycle {
+        if config.verbose {
+            log::warn(format!("Skipping symlink cycle: {}", link.display()));
+        }
+        return;
+    }
+
+    followed.push(parent);
+    walk_dir(link, depth, max_depth, config, followed, collected);
+    followed.pop();
+}
+
+/// Check if an entry is one of the changed paths, or a directory containing one
+fn is_changed_path(entry: &FileEntry, changed: &HashSet<PathBuf>) -> bool {
+    let Ok(path) = entry.path.canonicalize() else {
+        return false;
+    };
+
+    if entry.kind == EntryKind::Dir {
+        return changed.iter().any(|p| p.starts_with(&path));
+    }
+
+    changed.contains(&path)
+}
+
+/// List the files inside an archive as virtual entries
+/// Archives that can't be read are reported on stderr and listed without members
+fn list_archive_members(entry: &FileEntry) -> Vec<FileEntry> {
+    match archive::list_archive(&entry.path) {
+        Ok(members) => members
+            .into_iter()
+            .map(|member| FileEntry::from_archive_member(entry, member))
+            .collect(),
+        Err(e) => {
+            log::warn(format!(
+                "Failed to read archive {}: {}",
+                entry.path.display(),
+                e
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// Reorder entries level by level, all entries at depth 1 first, then depth 2 and so on
+/// Within a depth entries are ordered by the sort keys across all directories,
+/// the directions only flip the sort keys, never the depth order
+/// With SortKey::None entries keep their walk order within a depth
+/// If stable is true, entries that are still tied are ordered by path
+fn sort_breadth_first(entries: &mut [FileEntry], keys: &[(SortKey, Direction)], stable: bool) {
+    entries.sort_by(|a, b| {
+        a.depth
+            .cmp(&b.depth)
+            .then_with(|| compare_file_entries(a, b, keys))
+            .then_with(|| match stable {
+                true => a.path.cmp(&b.path),
+                false => Ordering::Equal,
+            })
+    });
+}
+
+/// Reorder entries by depth, shallow to deep, and then by path
+/// Unlike breadth first the sort keys are ignored, sorting on the path keeps
+/// entries of the same directory together within a depth
+/// Reversed only flips the path order, shallow entries still come first
+fn sort_by_depth(entries: &mut [FileEntry], reversed: bool) {
+    let direction = if reversed {
+        Direction::Desc
+    } else {
+        Direction::Asc
+    };
+
+    entries.sort_by(|a, b| {
+        a.depth
+            .cmp(&b.depth)
+            .then_with(|| direction.apply(a.path.cmp(&b.path)))
+    });
+}
+
+/// Get the keys to sort by
+/// A sort expression wins over the single sort key, the reverse flag flips every key
+/// With dirs_first, directories come first no matter the reverse flag
+/// With stable, the name breaks any remaining ties, always ascending, so entries
+/// of one directory never depend on the order the filesystem returns them in
+fn get_sort_keys(config: &Config) -> Vec<(SortKey, Direction)> {
+    let mut keys = match &config.sort_expr {
+        Some(SortExpr(keys)) => keys.clone(),
+        None => vec![(config.sort.unwrap_or_default(), Direction::Asc)],
+    };
+
+    if config.reversed {
+        keys = keys
+            .into_iter()
+            .map(|(key, direction)| (key, direction.reversed()))
+            .collect();
+    }
+
+    if config.dirs_first {
+        keys.insert(0, (SortKey::Dir, Direction::Asc));
+    }
+
+    if config.stable {
+        keys.push((SortKey::Name, Direction::Asc));
+    }
+
+    // sizes and times would have to be read, the name and type are free
+    if config.no_metadata {
+        keys.retain(|(key, _)| matches!(key, SortKey::Name | SortKey::Dir | SortKey::None));
+    }
+
+    keys
+}
+
+/// Compare two walked entries by each sort key in turn
+fn compare_dir_entries(a: &DirEntry, b: &DirEntry, keys: &[(SortKey, Direction)]) -> Ordering {
+    keys.iter()
+        .map(|&(key, direction)| {
+            let ordering = match key {
+                SortKey::Name => a.file_name().cmp(b.file_name()),
+                SortKey::Size => get_entry_len(a).cmp(&get_entry_len(b)),
+                SortKey::Dir => b.file_type().is_dir().cmp(&a.file_type().is_dir()),
+                SortKey::None => Ordering::Equal,
+                _ => get_sort_time(a, key).cmp(&get_sort_time(b, key)),
+            };
+            direction.apply(ordering)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compare two collected entries by each sort key in turn
+fn compare_file_entries(a: &FileEntry, b: &FileEntry, keys: &[(SortKey, Direction)]) -> Ordering {
+    keys.iter()
+        .map(|&(key, direction)| {
+            let ordering = match key {
+                SortKey::Name => a.name.cmp(&b.name),
+                SortKey::Size => a.size.cmp(&b.size),
+                SortKey::Dir => (b.kind == EntryKind::Dir).cmp(&(a.kind == EntryKind::Dir)),
+                SortKey::None => Ordering::Equal,
+                _ => get_entry_sort_time(a, key).cmp(&get_entry_sort_time(b, key)),
+            };
+            direction.apply(ordering)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// View files in a directory based on the provided configuration
+/// The listing is written to `out`, so it can go to stdout, a file or a buffer
+/// If no configuration is provided, the default configuration is used
+pub fn view_files(config: Option<Config>, out: &mut impl Write) -> io::Result<()> {
+    let config = config.unwrap_or_default();
+
+    if let Some(other) = &config.diff {
+        return write_diff(&config, other, out);
+    }
+
+    if config.total_only {
+        return write_total(&config, out);
+    }
+
+    let entries = collect_entries(&config);
+    view_entries(config, entries, out)
+}
+
+/// Render entries that were already collected with collect_entries, so a caller
+/// that needs them as well, like --exec, doesn't walk the directory twice
+/// --diff and --total-only don't list entries, view_files handles those
+pub fn view_entries(
+    mut config: Config,
+    entries: Vec<FileEntry>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    if config.count {
+        return writeln!(out, "{}", entries.len());
+    }
+
+    if config.summary_json {
+        return write_json(&Summary::new(&entries), config.json_pretty, out);
+    }
+
+    if let Some(sort) = config.extensions_summary {
+        return write_extensions_summary(&entries, sort, &config, out);
+    }
+
+    if let Some(limit) = config.ext_chart {
+        return write_extensions_chart(&entries, limit, &config, out);
+    }
+
+    if let Some(template) = &config.format_string {
+        return write_template(&entries, template, &config, out);
+    }
+
+    if !config.color_scale.is_empty() {
+        config.scale_range = ScaleRange::new(&entries, &config);
+    }
+
+    // a list of paths has no directory to put above it
+    if config.header
+        && config.paths.is_none()
+        && matches!(config.format, Format::Text | Format::Table)
+    {
+        writeln!(
+            out,
+            "{}",
+            get_header_path(&config).display().to_string().bold()
+        )?;
+    }
+
+    match config.format {
+        Format::Json => write_json(&to_records(&entries, &config), config.json_pretty, out),
+        Format::Ndjson => write_ndjson(&to_records(&entries, &config), out),
+        Format::Csv => write_csv(&to_records(&entries, &config), out),
+        Format::Table => write!(out, "{}", render_boxed_table(&entries, &config)),
+        Format::Text if config.group_by_dir => {
+            if !entries.is_empty() {
+                writeln!(out, "{}", render_grouped(group_by_parent(entries), &config))?;
+            }
+            Ok(())
+        }
+        Format::Text => {
+            for (entry, hidden) in limit_per_dir(entries, config.per_dir_limit) {
+                if config.table {
+                    let table = render_as_table(vec![entry.clone()], &config);
+                    writeln!(out, "{}", table)?;
+                } else {
+                    writeln!(out, "{}", render_as_row(&entry, &config))?;
+                }
+
+                if hidden > 0 {
+                    let dir = get_parent_dir(&entry);
+                    let more = format!("… and {} more in {}", hidden, dir.display());
+                    writeln!(out, "{}", more.dimmed())?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write one row per extension with the number of files and their total size
+/// Files without an extension are listed as "(none)"
+/// Example:
+/// .jpg     200   4.0 gib
+/// (none)     3  12.0 kib
+fn write_extensions_summary(
+    entries: &[FileEntry],
+    sort: ExtensionSort,
+    config: &Config,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let unit = config.unit.clone().unwrap_or_default();
+    let mut extensions: Vec<(String, ExtensionStats)> = get_extension_stats(entries)
+        .into_iter()
+        .map(|(ext, stats)| (get_extension_label(&ext), stats))
+        .collect();
+
+    // ties keep the alphabetical order of the map
+    match sort {
+        ExtensionSort::Count => extensions.sort_by_key(|(_, stats)| Reverse(stats.count)),
+        ExtensionSort::Size => extensions.sort_by_key(|(_, stats)| Reverse(stats.bytes)),
+    }
+
+    let ext_width = extensions
+        .iter()
+        .map(|(ext, _)| ext.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (ext, stats) in extensions {
+        let (size, suffix) = format_size_parts(stats.bytes, &unit, config);
+        writeln!(
+            out,
+            "{:<ext_width$}  {:>6}  {:>8} {}",
+            ext, stats.count, size, suffix
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Get the label of an extension in the extension reports
+/// Examples:
+/// "rs" -> ".rs"
+/// "" -> "(none)"
+fn get_extension_label(ext: &str) -> String {
+    match ext {
+        "" => "(none)".to_string(),
+        _ => format!(".{}", ext),
+    }
+}
+
+/// Write a horizontal bar chart of the total bytes per extension, largest first
+/// Only the top `limit` extensions get their own bar, the rest are added up
+/// under "(other)" at the bottom. Bars are scaled so the largest one fills the
+/// terminal, or 80 columns when not writing to one
+fn write_extensions_chart(
+    entries: &[FileEntry],
+    limit: usize,
+    config: &Config,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let unit = config.unit.clone().unwrap_or_default();
+    let mut extensions: Vec<(String, u64)> = get_extension_stats(entries)
+        .into_iter()
+        .map(|(ext, stats)| (get_extension_label(&ext), stats.bytes))
+        .collect();
+    // ties keep the alphabetical order of the map
+    extensions.sort_by_key(|(_, bytes)| Reverse(*bytes));
+
+    if extensions.len() > limit {
+        let other = extensions
+            .split_off(limit)
+            .iter()
+            .map(|(_, bytes)| bytes)
+            .sum();
+        extensions.push(("(other)".to_string(), other));
+    }
+
+    let sizes: Vec<String> = extensions
+        .iter()
+        .map(|(_, bytes)| {
+            let (size, suffix) = format_size_parts(*bytes, &unit, config);
+            format!("{} {}", size, suffix)
+        })
+        .collect();
+
+    let ext_width = extensions
+        .iter()
+        .map(|(ext, _)| ext.chars().count())
+        .max()
+        .unwrap_or(0);
+    let size_width = sizes.iter().map(|size| size.len()).max().unwrap_or(0);
+    let terminal_width = config.terminal_width.unwrap_or(80);
+    let bar_width = terminal_width
+        .saturating_sub(ext_width + size_width + 5)
+        .max(10);
+    let max = extensions
+        .iter()
+        .map(|(_, bytes)| *bytes)
+        .max()
+        .unwrap_or(0);
+
+    for ((ext, bytes), size) in extensions.iter().zip(&sizes) {
+        let bar = render_bar(*bytes, max, bar_width);
+        writeln!(
+            out,
+            "{:<ext_width$}  {}{}  {:>size_width$}",
+            ext,
+            bar.cyan(),
+            " ".repeat(bar_width - bar.chars().count()),
+            size
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Draw a bar of up to `width` cells for a value out of max, in eighths of a cell
+/// Any value above zero gets at least a sliver, so it doesn't look empty
+/// Examples:
+/// (50, 100, 4) -> "██"
+/// (1, 100, 4) -> "▏"
+/// (0, 100, 4) -> ""
+fn render_bar(value: u64, max: u64, width: usize) -> String {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    if value == 0 || max == 0 {
+        return String::new();
+    }
+
+    let eighths = ((value as f64 / max as f64) * (width * 8) as f64).round() as usize;
+    let eighths = eighths.max(1);
+
+    let mut bar = "█".repeat(eighths / 8);
+    let partial = eighths % 8;
+    if partial > 0 {
+        bar.push(PARTIAL[partial]);
+    }
+    bar
+}
+
+/// Write only the total size of everything in the listed directory, like `du -sh`
+/// Filters and max depth don't apply, every file below the directory is counted
+fn write_total(config: &Config, out: &mut impl Write) -> io::Result<()> {
+    let unit = config.unit.clone().unwrap_or_default();
+    let total = dir_size(
+        &normalize_start_dir(&config.dir),
+        config.verbose,
+        config.size_depth,
+        config.max_total_size,
+    );
+
+    if total.capped
+        && let Some(cap) = config.max_total_size
+    {
+        let (size, suffix) = format_size_parts(cap, &unit, config);
+        return writeln!(out, "> {} {}", size, suffix);
+    }
+
+    let marker = if total.is_partial() { "+" } else { "" };
+
+    if config.dual_units {
+        let (size, suffix) = format_size_parts(total.bytes, &get_scaled_unit(config), config);
+        return writeln!(out, "{} b{} ({} {})", total.bytes, marker, size, suffix);
+    }
+
+    let (size, suffix) = format_size_parts(total.bytes, &unit, config);
+    writeln!(out, "{} {}{}", size, suffix, marker)
+}
+
+/// Write how many entries were added, removed and changed, like "+12 -3 ~5"
+fn write_diff_stat(diff: &[DiffEntry], out: &mut impl Write) -> io::Result<()> {
+    let count = |change: Change| diff.iter().filter(|line| line.change == change).count();
+
+    writeln!(
+        out,
+        "{} {} {}",
+        format!("+{}", count(Change::Added)).green(),
+        format!("-{}", count(Change::Removed)).red(),
+        format!("~{}", count(Change::Changed)).yellow()
+    )
+}
+
+/// Get the absolute path of the listed directory, shown above the listing
+/// Falls back to the path as given when it can't be resolved
+fn get_header_path(config: &Config) -> PathBuf {
+    let dir = if config.trash {
+        trash::trash_dir().unwrap_or_default()
+    } else {
+        normalize_start_dir(&config.dir)
+    };
+
+    dir.canonicalize().unwrap_or(dir)
+}
+
+/// Write the differences between the listed directory and another directory
+/// Lines start with + for added, - for removed and ~ for changed entries,
+/// the max depth and filters apply to both sides
+fn write_diff(config: &Config, other: &str, out: &mut impl Write) -> io::Result<()> {
+    let unit = config.unit.clone().unwrap_or_default();
+
+    let other_config = Config {
+        dir: other.to_string(),
+        ..config.clone()
+    };
+    let base = collect_entries(&other_config);
+    let target = collect_entries(config);
+
+    let diff = diff_listings(
+        base,
+        &normalize_start_dir(other),
+        target,
+        &normalize_start_dir(&config.dir),
+    );
+
+    if config.diff_stat {
+        return write_diff_stat(&diff, out);
+    }
+
+    for line in diff {
+        let (prefix, color) = match line.change {
+            Change::Added => ("+", Color::Green),
+            Change::Removed => ("-", Color::Red),
+            Change::Changed => ("~", Color::Yellow),
+        };
+
+        let size = get_file_size(&line.entry, &unit, config).unwrap_or_else(|| "-".to_string());
+        let size = match &line.previous {
+            Some(previous) => {
+                let previous_size =
+                    get_file_size(previous, &unit, config).unwrap_or_else(|| "-".to_string());
+                format!("{} -> {}", previous_size, size)
+            }
+            None => size,
+        };
+
+        let date = get_file_modified_date(&line.entry).unwrap_or_else(|| "-".to_string());
+
+        let text = format!(
+            "{} {:<40} {:<20} {}",
+            prefix,
+            line.relative_path.display(),
+            date,
+            size
+        );
+        writeln!(out, "{}", text.color(color))?;
+    }
+
+    Ok(())
+}
+
+/// Convert entries into their machine readable records
+fn to_records(entries: &[FileEntry], config: &Config) -> Vec<EntryRecord> {
+    entries
+        .iter()
+        .map(|entry| EntryRecord::new(entry, config))
+        .collect()
+}
+
+/// Keep at most `limit` entries of each directory, the first ones in listing order
+/// Every kept entry comes with the number of entries of its directory left out,
+/// which is only non zero for the last one kept, so a note can follow it
+fn limit_per_dir(entries: Vec<FileEntry>, limit: Option<usize>) -> Vec<(FileEntry, usize)> {
+    let Some(limit) = limit else {
+        return entries.into_iter().map(|entry| (entry, 0)).collect();
+    };
+
+    let mut totals: HashMap<PathBuf, usize> = HashMap::new();
+    for entry in &entries {
+        *totals.entry(get_parent_dir(entry)).or_default() += 1;
+    }
+
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let parent = get_parent_dir(&entry);
+            let count = seen.entry(parent.clone()).or_default();
+            *count += 1;
+
+            match *count {
+                count if count < limit => Some((entry, 0)),
+                count if count == limit => Some((entry, totals[&parent] - limit)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Group entries by their parent directory, keeping the order in which
+/// each directory is first seen
+fn group_by_parent(entries: Vec<FileEntry>) -> Vec<(PathBuf, Vec<FileEntry>)> {
+    let mut groups: Vec<(PathBuf, Vec<FileEntry>)> = Vec::new();
+
+    for entry in entries {
+        let parent = get_parent_dir(&entry);
+        match groups.iter_mut().find(|(dir, _)| *dir == parent) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((parent, vec![entry])),
+        }
+    }
+
+    groups
+}
+
+/// Get the directory an entry is in, empty if it has none
+fn get_parent_dir(entry: &FileEntry) -> PathBuf {
+    entry
+        .path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Get the timestamp an entry is sorted by
+/// Entries without the timestamp sort as if they were from the unix epoch
+fn get_sort_time(entry: &DirEntry, sort: SortKey) -> SystemTime {
+    let metadata = entry.metadata().ok();
+
+    metadata
+        .and_then(|m| match sort {
+            SortKey::Created => m.created().ok(),
+            SortKey::Modified => m.modified().ok(),
+            SortKey::Accessed => m.accessed().ok(),
+            SortKey::Name | SortKey::Size | SortKey::Dir | SortKey::None => None,
+        })
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Get the timestamp a collected entry is sorted by
+/// Entries without the timestamp sort as if they were from the unix epoch
+/// Trash entries sort by when they were deleted, the time their date column shows
+fn get_entry_sort_time(entry: &FileEntry, sort: SortKey) -> SystemTime {
+    if let Some(deleted) = entry.deleted {
+        return deleted;
+    }
+
+    match sort {
+        SortKey::Created => entry.created,
+        SortKey::Modified => entry.modified,
+        SortKey::Accessed => entry.accessed,
+        SortKey::Name | SortKey::Size | SortKey::Dir | SortKey::None => None,
+    }
+    .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Write a value as JSON on one line, or indented over several with pretty on
+fn write_json(value: &impl Serialize, pretty: bool, out: &mut impl Write) -> io::Result<()> {
+    if pretty {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    } else {
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    writeln!(out)
+}
+
+/// Write every entry as a line filled in from the --format-string template
+/// Fields that aren't known for an entry, like the size of a broken link, are "-"
+fn write_template(
+    entries: &[FileEntry],
+    template: &Template,
+    config: &Config,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let unit = config.unit.clone().unwrap_or_default();
+    let format_time = |time: Option<SystemTime>| {
+        time.map(|t| format_time_with_style(t, config.time_style))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    for entry in entries {
+        let line = template.render(|field| match field {
+            Field::Name => get_plain_file_name(entry, config),
+            Field::Path => entry.path.to_string_lossy().into_owned(),
+            Field::Size => get_file_size(entry, &unit, config).unwrap_or_else(|| "-".to_string()),
+            Field::Bytes => entry
+                .size
+                .map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+            Field::Perms => entry.permissions.clone().unwrap_or_else(|| "-".to_string()),
+            Field::Mtime => format_time(entry.modified),
+            Field::Ctime => format_time(entry.created),
+            Field::Owner => get_display_owner(entry, config),
+        });
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Write the collected records as newline delimited JSON, one object per line
+/// Every line is flushed right away, so a slow reader gets entries as they come
+/// instead of in buffer sized chunks
+fn write_ndjson(records: &[EntryRecord], out: &mut impl Write) -> io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut *out, record)?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Write the collected records as CSV with a header row
+fn write_csv(records: &[EntryRecord], out: &mut impl Write) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+
+    for record in records {
+        writer.serialize(record).map_err(csv_to_io_error)?;
+    }
+
+    writer.flush()
+}
+
+/// Unwrap the io error inside a csv error, so a closed pipe is still recognized
+fn csv_to_io_error(e: csv::Error) -> io::Error {
+    if !e.is_io_error() {
+        return io::Error::other(e);
+    }
+
+    match e.into_kind() {
+        csv::ErrorKind::Io(e) => e,
+        kind => io::Error::other(format!("{:?}", kind)),
+    }
+}
+
+/// Get the file name with an icon, as the prefix before the name and the name itself
+/// Keeping them apart lets the name be truncated and styled on its own
+/// With canonicalize the name is the canonicalized path, otherwise just the file name
+/// With no_icons the icon is left out
+/// With classify the name ends in a type indicator like `ls -F`
+/// Files inside an archive are indented under the archive
+fn get_file_name(entry: &FileEntry, config: &Config) -> (String, String) {
+    let mut name = get_plain_file_name(entry, config);
+    if config.classify {
+        name.push_str(get_classify_suffix(entry));
+    }
+    if entry.broken_link {
+        name.push('!');
+    }
+    if config.show_mounts && entry.kind == EntryKind::Dir && is_mount_point(&entry.path) {
+        match mount_fs_type(&entry.path) {
+            Some(fs_type) => name.push_str(&format!(" [mount: {}]", fs_type)),
+            None => name.push_str(" [mount]"),
+        }
+    }
+
+    let indent = if entry.kind == EntryKind::ArchiveMember {
+        "  "
+    } else {
+        ""
+    };
+
+    if config.no_icons {
+        return (indent.to_string(), name);
+    }
+
+    // telling empty directories apart means reading them, which no_metadata rules out
+    let icon = get_file_icon(entry, &config.theme, !config.no_metadata);
+
+    (format!("{indent}{icon} "), name)
+}
+
+/// Get the type indicator --classify puts after a name
+/// Examples:
+/// directory -> "/"
+/// symlink -> "@"
+/// socket -> "="
+/// named pipe -> "|"
+/// executable file -> "*"
+/// anything else -> ""
+fn get_classify_suffix(entry: &FileEntry) -> &'static str {
+    match entry.kind {
+        EntryKind::Dir => "/",
+        EntryKind::Symlink => "@",
+        EntryKind::Socket => "=",
+        EntryKind::Fifo => "|",
+        EntryKind::File if is_executable(entry) => "*",
+        _ => "",
+    }
+}
+
+/// Check if any of the execute bits of a file are set, going by its rwx string
+/// A setuid, setgid or sticky bit in lowercase stands in for a set execute bit
+/// Entries without permissions are never executable
+fn is_executable(entry: &FileEntry) -> bool {
+    entry.permissions.as_deref().is_some_and(|permissions| {
+        permissions
+            .chars()
+            .skip(2)
+            .step_by(3)
+            .any(|c| matches!(c, 'x' | 's' | 't'))
+    })
+}
+
+/// Count the characters of a string that show up on screen, skipping color escapes
+/// Characters are grapheme clusters, the same as names are truncated by
+/// Examples:
+/// "plain" -> 5
+/// "\x1b[32mX\x1b[0m " -> 2
+/// "cafe\u{301}" -> 4
+fn visible_len(s: &str) -> usize {
+    let mut visible = String::new();
+    let mut in_escape = false;
+
+    for c in s.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (false, _) => visible.push(c),
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+        }
+    }
+
+    visible.graphemes(true).count()
+}
+
+/// Get the file name without an icon
+/// If canonicalize is true, return the canonicalized path
+/// With relative_to, the path relative to that directory is returned instead,
+/// or the absolute path if the two have nothing in common
+fn get_plain_file_name(entry: &FileEntry, config: &Config) -> String {
+    if let Some(base) = &config.relative_to
+        && entry.kind != EntryKind::ArchiveMember
+    {
+        let path = std::path::absolute(&entry.path).unwrap_or_else(|_| entry.path.clone());
+        return relative_path(&path, Path::new(base))
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned();
+    }
+
+    if !config.canonicalize {
+        return entry.name.clone();
+    }
+
+    // canonicalize fails on broken links and symlink loops, show the path as is then
+    entry
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| entry.path.clone())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Get the components of an absolute path with any ".." taken off lexically,
+/// std::path::absolute leaves them in
+/// Example:
+/// "/home/user/../src" -> ["/", "home", "src"]
+fn lexical_components(path: &Path) -> Vec<Component<'_>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            // never above the root
+            Component::ParentDir if components.len() > 1 => {
+                components.pop();
+            }
+            Component::ParentDir | Component::CurDir => {}
+            component => components.push(component),
+        }
+    }
+    components
+}
+
+/// Get a path relative to a base directory, going up with ".." where needed
+/// Both paths have to be absolute. Paths that share nothing but the root have no
+/// relative path worth showing
+/// Examples:
+/// ("/home/user/src/main.rs", "/home/user") -> "src/main.rs"
+/// ("/home/user/src/main.rs", "/home/user/docs") -> "../src/main.rs"
+/// ("/home/user", "/home/user") -> "."
+/// ("/etc/passwd", "/home/user") -> None
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = lexical_components(path);
+    let base = lexical_components(base);
+
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if common <= 1 {
+        return None;
+    }
+
+    let mut relative: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(&path[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+
+    Some(relative)
+}
+
+/// Get an icon based on the file type from the theme
+/// The built-in theme uses:
+/// Directory:  (blue)
+/// Directory not empty:  (blue)
+/// Directory symlink:  (cyan)
+/// Symlink:  (cyan)
+/// File:  (green)
+/// Files with an extension in the theme use that icon instead of the generic file icon
+/// The type comes from the entry, only with probe_dirs are directories read to
+/// tell empty ones apart, otherwise every directory gets the plain icon
+fn get_file_icon(entry: &FileEntry, theme: &Theme, probe_dirs: bool) -> String {
+    let path = entry.path.as_path();
+
+    let is_dir = entry.kind == EntryKind::Dir;
+    let is_symlink = entry.kind == EntryKind::Symlink;
+    let is_file = matches!(entry.kind, EntryKind::File | EntryKind::ArchiveMember);
+    let is_nonempty_dir = is_dir
+        && (!probe_dirs
+            || fs::read_dir(path)
+                .map(|mut r| r.next().is_some())
+                .unwrap_or(false));
+
+    let ext_icon = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| theme.extension_icon(ext));
+
+    match entry.kind {
+        EntryKind::Socket => return theme.socket.render(),
+        EntryKind::Fifo => return theme.fifo.render(),
+        EntryKind::BlockDevice => return theme.block_device.render(),
+        EntryKind::CharDevice => return theme.char_device.render(),
+        _ => {}
+    }
+
+    match (is_symlink, is_dir, is_file, is_nonempty_dir) {
+        (true, _, _, _) => theme.symlink.render(),
+        (_, true, _, true) => theme.dir.render(),
+        (_, true, _, false) => theme.empty_dir.render(),
+        (_, _, true, _) => ext_icon.unwrap_or(&theme.file).render(),
+        _ => String::new(),
+    }
+}
+
+/// Check if a file is hidden (starts with a dot)
+/// Examples:
+/// .hidden -> true
+/// visible -> false
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .map(|s| s.starts_with('.'))
+        .unwrap_or(false)
+}
+
+/// Check if a file fits the --min-size and --max-size filters
+/// Only files are filtered, everything else is always within range
+fn is_within_size_range(entry: &FileEntry, config: &Config) -> bool {
+    if entry.kind != EntryKind::File {
+        return true;
+    }
+
+    let size = entry.size.unwrap_or(0);
+    config.min_size.is_none_or(|min| size >= min) && config.max_size.is_none_or(|max| size <= max)
+}
+
+/// Get the text that --glob, --regex and --exclude match against
+/// That is the file name, or with match_path the path relative to the listed directory
+/// Examples:
+/// "./src/config.rs" -> "config.rs"
+/// "./src/config.rs" with match_path -> "src/config.rs"
+fn get_match_input<'a>(path: &'a Path, start: &Path, config: &Config) -> Cow<'a, str> {
+    if config.match_path {
+        path.strip_prefix(start).unwrap_or(path).to_string_lossy()
+    } else {
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    }
+}
+
+/// Check if an entry fits the --glob and --regex filters
+/// Without any patterns every entry matches
+fn matches_patterns(path: &Path, start: &Path, config: &Config) -> bool {
+    if config.globs.is_empty() && config.regex.is_none() {
+        return true;
+    }
+
+    let input = get_match_input(path, start, config);
+    (config.globs.is_empty() || config.globs.iter().any(|glob| glob.is_match(&*input)))
+        && config
+            .regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(&input))
+}
+
+/// Check if an entry fits the --ext and --ext-file filters
+/// With extensions to allow, entries without one, directories included, are left out
+fn has_allowed_extension(entry: &FileEntry, config: &Config) -> bool {
+    if config.extensions.is_empty() {
+        return true;
+    }
+
+    entry.extension().is_some_and(|ext| {
+        config
+            .extensions
+            .iter()
+            .any(|allowed| match config.ignore_case {
+                true => allowed.eq_ignore_ascii_case(&ext),
+                false => *allowed == ext,
+            })
+    })
+}
+
+/// Read the extensions listed in an --ext-file, one per line
+/// A leading dot is optional, blank lines and lines starting with # are skipped
+/// Example:
+/// # images
+/// .jpg
+/// png
+/// -> ["jpg", "png"]
+pub fn read_extension_file(path: &str) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read extension file {}: {}", path, e))?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(normalize_extension)
+        .collect())
+}
+
+/// Strip the optional leading dot off an extension given on the command line
+/// Examples:
+/// ".rs" -> "rs"
+/// "rs" -> "rs"
+pub fn normalize_extension(ext: &str) -> String {
+    ext.strip_prefix('.').unwrap_or(ext).to_string()
+}
+
+/// Check if an entry matches one of the --exclude globs
+fn is_excluded(path: &Path, start: &Path, config: &Config) -> bool {
+    if config.exclude.is_empty() {
+        return false;
+    }
+
+    let input = get_match_input(path, start, config);
+    config.exclude.iter().any(|glob| glob.is_match(&*input))
+}
+
+/// Directory names of version control metadata left out by --exclude-vcs
+const VCS_DIRS: [&str; 4] = [".git", ".hg", ".svn", ".bzr"];
+
+/// Check if an entry below the listed directory is a version control directory
+/// Examples:
+/// "./.git" -> true
+/// "./src/.git" file, as in a git worktree -> false
+/// "./notes.git" -> false
+fn is_vcs_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| VCS_DIRS.contains(&name))
+}
+
+/// Check if a file fits the --modified-after and --modified-before filters
+/// Only files are filtered, files without a modified time never match a filter
+fn is_within_date_range(entry: &FileEntry, config: &Config) -> bool {
+    if entry.kind != EntryKind::File
+        || (config.modified_after.is_none() && config.modified_before.is_none())
+    {
+        return true;
+    }
+
+    entry.modified.is_some_and(|modified| {
+        config.modified_after.is_none_or(|after| modified >= after)
+            && config
+                .modified_before
+                .is_none_or(|before| modified < before)
+    })
+}
+
+/// Check if an entry fits the --owner and --group filters
+/// Entries whose owner can't be read never match a filter
+fn is_owned_by(entry: &FileEntry, config: &Config) -> bool {
+    if config.user.is_none() && config.group.is_none() {
+        return true;
+    }
+
+    file_ids(&entry.path).is_some_and(|(uid, gid)| {
+        config.user.is_none_or(|user| uid == user) && config.group.is_none_or(|group| gid == group)
+    })
+}
+
+/// Check if a path points to a hidden file, by its file name
+fn is_hidden_path(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Check if a file is larger than the given limit in bytes
+/// Directories are never considered too large so we still descend into them
+fn exceeds_size_limit(entry: &walkdir::DirEntry, limit: Option<u64>) -> bool {
+    let Some(limit) = limit else {
+        return false;
+    };
+
+    entry.file_type().is_file() && get_entry_len(entry).is_some_and(|size| size > limit)
+}
+
+/// Get the file modification date as a formatted string
+/// If the modification date cannot be determined, return None
+fn get_file_modified_date(entry: &FileEntry) -> Option<String> {
+    entry.modified.map(format_system_time)
+}
+
+/// Get the file access date as a formatted string
+/// Access times are often not tracked (noatime mounts), so if the access date
+/// cannot be determined or is the same as the modified or created date, return None
+fn get_file_accessed_date(entry: &FileEntry) -> Option<String> {
+    let accessed = entry.accessed?;
+
+    if entry.modified == Some(accessed) || entry.created == Some(accessed) {
+        return None;
+    }
+
+    Some(format_system_time(accessed))
+}
+
+/// Get the date of a time field as a formatted string
+/// Relative times are used when configured. A creation date that fell back to
+/// another timestamp is marked with a prefix, "m " for modified and "a " for accessed
+fn get_file_date(entry: &FileEntry, time: TimeField, config: &Config) -> Option<String> {
+    let format = |t: SystemTime| format_time_with_style(t, config.time_style);
+
+    // trash listings show when the file was deleted instead
+    if let Some(deleted) = entry.deleted {
+        return Some(format(deleted));
+    }
+
+    match time {
+        TimeField::Created => {
+            let (created, fallback) = get_creation_time(entry, config.strict_created)?;
+            let marker = fallback.map(|c| format!("{} ", c)).unwrap_or_default();
+            Some(format!("{}{}", marker, format(created)))
+        }
+        TimeField::Modified => entry.modified.map(format),
+        TimeField::Accessed => get_file_accessed_date(entry).and(entry.accessed.map(format)),
+    }
+}
+
+/// Get the creation time, falling back to the modified and then the accessed time
+/// on filesystems that don't record it. The second value is the letter of the
+/// timestamp that was used instead, or None if it is the real creation time
+/// If strict is true there is no fallback
+fn get_creation_time(entry: &FileEntry, strict: bool) -> Option<(SystemTime, Option<char>)> {
+    if let Some(created) = entry.created {
+        return Some((created, None));
+    }
+
+    if strict {
+        return None;
+    }
+
+    entry
+        .modified
+        .map(|modified| (modified, Some('m')))
+        .or(entry.accessed.map(|accessed| (accessed, Some('a'))))
+}
+
+/// Get the raw timestamp for a time field
+fn get_file_time(entry: &FileEntry, time: TimeField, config: &Config) -> Option<SystemTime> {
+    if entry.deleted.is_some() {
+        return entry.deleted;
+    }
+
+    match time {
+        TimeField::Created => get_creation_time(entry, config.strict_created).map(|(t, _)| t),
+        TimeField::Modified => entry.modified,
+        TimeField::Accessed => entry.accessed,
+    }
+}
+
+/// Color the date column on the --color-scale gradient when it's on, by age otherwise
+fn color_date(date: String, entry: &FileEntry, time: TimeField, config: &Config) -> ColoredString {
+    let time = get_file_time(entry, time, config);
+    if !config.color_scale.contains(&ScaleColumn::Date) {
+        return color_by_age(date, time);
+    }
+
+    let secs = |time: SystemTime| {
+        time.duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64())
+    };
+    match (time, config.scale_range.time) {
+        (Some(time), Some((min, max))) => color_by_scale(date, secs(time), secs(min), secs(max)),
+        _ => date.normal(),
+    }
+}
+
+/// Color the size column on the --color-scale gradient when it's on
+/// Files at or above the --warn-size threshold are flagged in bold red instead
+fn color_size(size: String, entry: &FileEntry, config: &Config) -> ColoredString {
+    if let (Some(threshold), Some(bytes)) = (config.warn_size, entry.size)
+        && entry.kind == EntryKind::File
+        && bytes >= threshold
+    {
+        return size.red().bold();
+    }
+
+    if !config.color_scale.contains(&ScaleColumn::Size) {
+        return size.normal();
+    }
+
+    match (entry.size, config.scale_range.size) {
+        (Some(bytes), Some((min, max))) => {
+            color_by_scale(size, bytes as f64, min as f64, max as f64)
+        }
+        _ => size.normal(),
+    }
+}
+
+/// Color text by where a value falls between the smallest and largest value,
+/// going from blue at the bottom through yellow to red at the top
+/// When all values are the same, everything is at the bottom
+fn color_by_scale(text: String, value: f64, min: f64, max: f64) -> ColoredString {
+    const STOPS: [(f64, f64, f64); 3] = [
+        (90.0, 150.0, 255.0),
+        (240.0, 220.0, 90.0),
+        (255.0, 85.0, 85.0),
+    ];
+
+    let position = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    // the lower half blends the first two stops, the upper half the last two
+    let (from, to, t) = if position < 0.5 {
+        (STOPS[0], STOPS[1], position * 2.0)
+    } else {
+        (STOPS[1], STOPS[2], position * 2.0 - 1.0)
+    };
+    let blend = |a: f64, b: f64| (a + (b - a) * t).round() as u8;
+
+    text.truecolor(
+        blend(from.0, to.0),
+        blend(from.1, to.1),
+        blend(from.2, to.2),
+    )
+}
+
+/// Color a formatted date by how recent it is
+/// Within the last hour: bright
+/// Within the last day: normal
+/// Older: dimmed
+fn color_by_age(date: String, time: Option<SystemTime>) -> ColoredString {
+    let Some(age) = time.and_then(|t| SystemTime::now().duration_since(t).ok()) else {
+        return date.normal();
+    };
+
+    match age.as_secs() {
+        0..3600 => date.bright_white().bold(),
+        3600..86400 => date.normal(),
+        _ => date.dimmed(),
+    }
+}
+
+/// Format a system time as how long ago it was, in the largest whole unit
+/// Examples:
+/// 30 seconds ago -> "just now"
+/// 90 minutes ago -> "1 hour ago"
+/// 3 days ago -> "3 days ago"
+fn format_relative_time(system_time: SystemTime) -> String {
+    let Ok(age) = SystemTime::now().duration_since(system_time) else {
+        return "in the future".to_string();
+    };
+
+    let secs = age.as_secs();
+    let (count, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        86400..2592000 => (secs / 86400, "day"),
+        2592000..31536000 => (secs / 2592000, "month"),
+        _ => (secs / 31536000, "year"),
+    };
+
+    format!(
+        "{} {}{} ago",
+        count,
+        unit,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+/// Format a system time in one of the --time-style presets
+/// Like ls, iso counts dates within the last six months as recent
+/// Examples:
+/// (2024-05-01 12:34:56, TimeStyle::LongIso) -> "2024-05-01 12:34"
+/// (a week ago, TimeStyle::Iso) -> "05-01 12:34"
+/// (a year ago, TimeStyle::Iso) -> "2023-05-01"
+fn format_time_with_style(system_time: SystemTime, style: TimeStyle) -> String {
+    const SIX_MONTHS: Duration = Duration::from_secs(183 * 24 * 60 * 60);
+    let datetime: DateTime<Local> = system_time.into();
+
+    match style {
+        TimeStyle::Locale => format_system_time(system_time),
+        TimeStyle::Relative => format_relative_time(system_time),
+        TimeStyle::Iso => {
+            let recent = SystemTime::now()
+                .duration_since(system_time)
+                .is_ok_and(|age| age < SIX_MONTHS);
+            let format = if recent { "%m-%d %H:%M" } else { "%Y-%m-%d" };
+            datetime.format(format).to_string()
+        }
+        TimeStyle::LongIso => datetime.format("%Y-%m-%d %H:%M").to_string(),
+        TimeStyle::FullIso => datetime.format("%Y-%m-%d %H:%M:%S%.9f %z").to_string(),
+    }
+}
+
+/// Format a system time with the locale date and time representation
+fn format_system_time(system_time: SystemTime) -> String {
+    let datetime: DateTime<Local> = system_time.into();
+
+    datetime.formatl("%x %X", LOCALE).to_string()
+}
+
+/// Format a system time as RFC 3339 in local time, for machine readable output
+/// Example:
+/// 2024-05-01 12:34:56 -> "2024-05-01T12:34:56+02:00"
+fn format_rfc3339(system_time: SystemTime) -> String {
+    let datetime: DateTime<Local> = system_time.into();
+
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+fn get_file_extension(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?;
+    Some(ext.to_string())
+}
+
+/// Get the character encoding of a text file
+/// Directories and binary files return None
+fn get_file_encoding(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+
+    encoding::detect_encoding(path)
+}
+
+/// Count the immediate entries of a directory
+/// Hidden entries are only counted when hidden files are shown
+/// Files and unreadable directories return None
+fn get_child_count(path: &Path, show_hidden: bool) -> Option<usize> {
+    if !path.is_dir() {
+        return None;
+    }
+
+    let count = fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter(|child| show_hidden || !child.file_name().to_string_lossy().starts_with('.'))
+        .count();
+
+    Some(count)
+}
+
+/// Get the number of extended attributes on an entry, like `ls -l@`
+/// Symlinks are not followed, and entries without attributes return None
+/// Examples:
+/// quarantined download -> Some(1)
+/// plain file -> None
+fn get_xattr_count(path: &Path) -> Option<usize> {
+    let count = xattr::list(path).ok()?.count();
+
+    (count > 0).then_some(count)
+}
+
+/// Get the file permissions as a rwx string
+/// Examples:
+/// rwxr-xr-x -> "rwxr-xr-x"
+fn get_file_permissions(metadata: Option<&fs::Metadata>) -> Option<String> {
+    let mode = metadata?.permissions().mode();
+
+    // this somehow gets the last 9 bits
+    // idk how because im a retard
+    let perms = mode & 0o777;
+
+    // setuid, setgid and sticky replace the x of owner, group and others, like ls
+    let to_rwx = |_, shift: u8, special: bool, special_char: char| {
+        // this is magic to me
+        let bits = (perms >> shift) & 0o7;
+        let exec = match (special, bits & 0o1 != 0) {
+            (true, true) => special_char,
+            (true, false) => special_char.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        };
+        format!(
+            "{}{}{}",
+            if bits & 0o4 != 0 { 'r' } else { '-' },
+            if bits & 0o2 != 0 { 'w' } else { '-' },
+            exec
+        )
+    };
+
+    let owner = to_rwx(perms, 6, mode & 0o4000 != 0, 's');
+    let group = to_rwx(perms, 3, mode & 0o2000 != 0, 's');
+    let others = to_rwx(perms, 0, mode & 0o1000 != 0, 't');
+
+    Some(format!("{}{}{}", owner, group, others))
+}
+
+/// Color each character of a permission string so patterns are easy to scan
+/// r is yellow, w is red, x is green, special bits are magenta and - is dimmed
+/// Examples:
+/// "rwxr-xr-x" -> "rwxr-xr-x" with every character colored
+fn color_permissions(permissions: &str) -> String {
+    permissions
+        .chars()
+        .map(|c| {
+            let c_str = c.to_string();
+            match c {
+                'r' => c_str.yellow(),
+                'w' => c_str.red(),
+                'x' => c_str.green(),
+                's' | 'S' | 't' | 'T' => c_str.magenta(),
+                '-' => c_str.dimmed(),
+                _ => c_str.normal(),
+            }
+            .to_string()
+        })
+        .collect()
+}
+
+/// Get the file size in the specified unit
+/// If the file size cannot be determined, return None
+/// Examples:
+/// 1024 bytes with Unit::KB -> "1 kib"
+/// 1048576 bytes with Unit::MB -> "1 mib"
+/// 2048 bytes with Unit::KB and long units -> "2 kilobytes"
+fn get_file_size(entry: &FileEntry, unit: &Unit, config: &Config) -> Option<String> {
+    let (size, suffix) = get_file_size_parts(entry, unit, config)?;
+
+    Some(format!("{} {}", size, suffix))
+}
+
+/// Get the file size as a number and a unit suffix, so they can be aligned separately
+/// Sizes in auto units or with aligned sizes on get one decimal, plain bytes never do
+/// Examples:
+/// 1536 bytes with Unit::Auto -> ("1.5", "kib")
+/// 1536 bytes with Unit::KB -> ("1", "kib")
+/// 1536 bytes with Unit::KB and aligned sizes -> ("1.5", "kib")
+fn get_file_size_parts(
+    entry: &FileEntry,
+    unit: &Unit,
+    config: &Config,
+) -> Option<(String, String)> {
+    let bytes = entry.size?;
+
+    // counting stopped once the total went over the cap, so only the cap is known
+    if entry.size_partial
+        && let Some(cap) = config.max_total_size
+        && bytes > cap
+    {
+        let (size, suffix) = format_size_parts(cap, unit, config);
+        return Some((format!("> {}", size), suffix));
+    }
+
+    let (size, suffix) = format_size_parts(bytes, unit, config);
+
+    // a partial directory total is only a lower bound
+    if entry.size_partial {
+        return Some((size, format!("{}+", suffix)));
+    }
+
+    Some((size, suffix))
+}
+
+/// Format a number of bytes as a number and a unit suffix
+/// With a block size, the size is a count of blocks, rounded up like `du`
+fn format_size_parts(size_in_bytes: u64, unit: &Unit, config: &Config) -> (String, String) {
+    if let Some(block_size) = config.block_size {
+        let blocks = size_in_bytes.div_ceil(block_size);
+        let suffix = match (config.long_units, blocks) {
+            (false, _) => "blk",
+            (true, 1) => "block",
+            (true, _) => "blocks",
+        };
+        return (blocks.to_string(), suffix.to_string());
+    }
+
+    let decimals = matches!(unit, Unit::Auto) || config.align_sizes;
+    let unit = unit.resolve(size_in_bytes, config.si);
+    let unit_bytes = unit.bytes(config.si);
+
+    let (size, count) = if decimals && !matches!(unit, Unit::Bytes) {
+        let size = size_in_bytes as f64 / unit_bytes as f64;
+        (format!("{:.1}", size), if size == 1.0 { 1 } else { 2 })
+    } else {
+        let size = config.size_rounding.divide(size_in_bytes, unit_bytes);
+        (size.to_string(), size)
+    };
+
+    let suffix = if config.long_units {
+        long_size_unit(&unit, count, LOCALE)
+    } else if config.si {
+        si_size_unit(&unit).to_string()
+    } else {
+        normalize_size_unit(&unit).to_string()
+    };
+
+    (size, suffix)
+}
+
+/// Get the raw file size in bytes
+/// With dir_size on, directories report the total size of their contents,
+/// along with whether that total is only partial
+/// If the file size cannot be determined, return None
+fn get_file_size_bytes(
+    entry: &walkdir::DirEntry,
+    metadata: Option<&fs::Metadata>,
+    config: &Config,
+) -> (Option<u64>, bool) {
+    // the walk doesn't follow links, so neither does the size of a linked directory
+    if config.dir_size && entry.file_type().is_dir() {
+        let size = dir_size(
+            entry.path(),
+            config.verbose,
+            config.size_depth,
+            config.max_total_size,
+        );
+        return (Some(size.bytes), size.is_partial());
+    }
+
+    (metadata.map(|m| m.len()), false)
+}
+
+/// Get the length of the entry itself from its metadata
+fn get_entry_len(entry: &walkdir::DirEntry) -> Option<u64> {
+    entry.path().metadata().ok().map(|m| m.len())
+}
+
+/// Render a single file entry as a formatted row
+fn render_as_row(entry: &FileEntry, config: &Config) -> String {
+    // without metadata there are no columns, so the name is neither cut nor padded
+    if config.no_metadata {
+        let (prefix, name) = get_file_name(entry, config);
+        return format!("{}{}", prefix, style_file_name(name, entry, config));
+    }
+
+    let mut row = String::new();
+    for (i, column) in get_columns(config).into_iter().enumerate() {
+        if i > 0 {
+            row.push(' ');
+        }
+
+        let width = column.width(config);
+        if column == Column::Name {
+            let indent = visible_len(&row) + width;
+            row.push_str(&get_name_cell(entry, width, indent, config));
+            continue;
+        }
+
+        // the color escapes would count towards the width, so pad by hand
+        let cell = get_cell(entry, column, Some(width), config);
+        let padding = " ".repeat(width.saturating_sub(visible_len(&cell)));
+        if column.right_aligned() {
+            row.push_str(&padding);
+            row.push_str(&cell);
+        } else {
+            row.push_str(&cell);
+            row.push_str(&padding);
+        }
+    }
+
+    if is_modified_since(entry, config) {
+        row.push_str(&format!(" {}", "*".yellow().bold()));
+    }
+
+    row
+}
+
+/// Get the columns of a row, the ones picked with --columns or else the name,
+/// extension, date, permissions and size followed by the optional columns turned on
+fn get_columns(config: &Config) -> Vec<Column> {
+    if !config.columns.is_empty() {
+        return config.columns.clone();
+    }
+
+    // without metadata there is nothing to fill the other fixed columns with
+    let mut columns = if config.no_metadata {
+        vec![Column::Name]
+    } else {
+        vec![
+            Column::Name,
+            Column::Ext,
+            Column::Date(None),
+            Column::Perms,
+            Column::Size,
+        ]
+    };
+
+    let optional = [
+        (config.dual_units, Column::Scaled),
+        (config.encoding, Column::Encoding),
+        (config.child_count, Column::Children),
+        (config.xattr, Column::Xattrs),
+        (config.percent, Column::Share),
+        (config.drift, Column::Drift),
+        (config.age_bucket, Column::Age),
+        (config.sparse, Column::Allocated),
+        (config.owner, Column::Owner),
+        (config.file_flags, Column::Flags),
+        (config.hash || config.duplicates, Column::Hash),
+    ];
+    columns.extend(
+        optional
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, column)| column),
+    );
+
+    columns
+}
+
+/// Get the name cell of a row, with the icon, cut short or padded to the width
+/// With wrap, a name that doesn't fit is kept whole and the rest of the row goes
+/// on the next line, indented by indent
+fn get_name_cell(entry: &FileEntry, width: usize, indent: usize, config: &Config) -> String {
+    let (prefix, name) = get_file_name(entry, config);
+
+    // the icon's color escapes don't take up any room, so the name is truncated
+    // and padded by hand on what is actually visible
+    let prefix_width = visible_len(&prefix);
+    // a canonical path cut at the end only shows the same few parent directories
+    // for every entry, so it loses its middle instead and keeps the file name
+    let max_len = (width - 1).saturating_sub(prefix_width);
+    // with wrap, a name that doesn't fit is kept whole on a line of its own and the
+    // columns go on the next line, indented to where they start on every other row
+    let wrapped = config.wrap && name.graphemes(true).count() > max_len;
+    let name = if wrapped {
+        name
+    } else if config.canonicalize {
+        name.truncate_middle(max_len)
+    } else {
+        name.truncate_ellipsis(max_len)
+    };
+    let padding = if wrapped {
+        format!("\n{}", " ".repeat(indent))
+    } else {
+        " ".repeat(width.saturating_sub(prefix_width + name.graphemes(true).count()))
+    };
+
+    format!(
+        "{}{}{}",
+        prefix,
+        style_file_name(name, entry, config),
+        padding
+    )
+}
+
+/// Get the colored text of a cell in a column
+/// With a width, cells that could run over it are cut short to fit, the table
+/// fits its columns to the cells instead and passes None
+fn get_cell(entry: &FileEntry, column: Column, width: Option<usize>, config: &Config) -> String {
+    let fit = |text: String, width: Option<usize>| match width {
+        Some(width) => text.truncate_ellipsis(width),
+        None => text,
+    };
+
+    match column {
+        Column::Name => {
+            let (prefix, name) = get_file_name(entry, config);
+            format!("{}{}", prefix, style_file_name(name, entry, config))
+        }
+        Column::Ext => get_display_extension(entry),
+        Column::Date(time) => {
+            let time = time.or(config.time).unwrap_or_default();
+            let date = fit(get_display_date(entry, time, config), width.map(|w| w - 1));
+            color_date(date, entry, time, config).to_string()
+        }
+        Column::Perms => {
+            let permissions = entry.permissions.clone().unwrap_or_else(|| "-".to_string());
+            color_permissions(&fit(permissions, width.map(|w| w - 1)))
+        }
+        Column::Size => color_size(get_display_size(entry, config), entry, config).to_string(),
+        Column::Scaled => {
+            let scaled = get_display_scaled_size(entry, config);
+            color_size(scaled, entry, config).to_string()
+        }
+        Column::Encoding => get_file_encoding(&entry.path).unwrap_or_else(|| "-".to_string()),
+        Column::Children => get_display_child_count(entry, config),
+        Column::Xattrs => get_display_xattrs(entry),
+        Column::Share => get_display_percent(entry),
+        Column::Drift => get_display_drift(entry).to_string(),
+        Column::Age => get_display_age_bucket(entry).to_string(),
+        Column::Allocated => get_display_sparse(entry, config).to_string(),
+        Column::Owner => fit(get_display_owner(entry, config), width),
+        Column::Flags => file_flags(&entry.path).unwrap_or_else(|| "-".to_string()),
+        Column::Hash => get_display_hash(entry).to_string(),
+    }
+}
+
+/// Check if an entry was modified after the --since time
+/// Entries without a modified time are never marked
+fn is_modified_since(entry: &FileEntry, config: &Config) -> bool {
+    matches!((config.since, entry.modified), (Some(since), Some(modified)) if modified > since)
+}
+
+/// Make the file name bold, in the color of the first matching theme rule if any
+/// With --fuzzy, the characters the query matched are highlighted
+fn style_file_name(name: String, entry: &FileEntry, config: &Config) -> String {
+    // entries only listed as context around a match stay in the background
+    if entry.context {
+        return name.dimmed().to_string();
+    }
+
+    let style = |text: String| {
+        if entry.broken_link {
+            return text.red().bold();
+        }
+
+        match config.theme.rule_color(&entry.name) {
+            Some(color) => text.color(color).bold(),
+            None => text.bold(),
+        }
+    };
+
+    let Some((_, indices)) = config
+        .fuzzy
+        .as_ref()
+        .and_then(|query| fuzzy_matcher(config.ignore_case).fuzzy_indices(&name, query))
+    else {
+        return style(name).to_string();
+    };
+
+    // style runs of matched and unmatched characters, not every character on its own
+    let mut styled = String::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in name.chars().enumerate() {
+        let matched = indices.contains(&index);
+        if matched != run_matched && !run.is_empty() {
+            styled.push_str(&style_fuzzy_run(
+                std::mem::take(&mut run),
+                run_matched,
+                style,
+            ));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    styled.push_str(&style_fuzzy_run(run, run_matched, style));
+
+    styled
+}
+
+/// Style a run of characters of a name, underlined in yellow when --fuzzy matched them
+fn style_fuzzy_run(run: String, matched: bool, style: impl Fn(String) -> ColoredString) -> String {
+    if matched {
+        run.yellow().bold().underline().to_string()
+    } else {
+        style(run).to_string()
+    }
+}
+
+/// Get the extension as shown in the extension column
+/// Examples:
+/// main.rs -> ".rs"
+/// Makefile -> ".-"
+/// src/ -> "-"
+fn get_display_extension(entry: &FileEntry) -> String {
+    let ext = entry.extension().unwrap_or_else(|| "-".to_string());
+    let is_dir = entry.path.is_dir();
+
+    format!("{}{}", if is_dir { "" } else { "." }, ext)
+}
+
+/// Get the date of a time field as shown in a date column, relative or absolute
+fn get_display_date(entry: &FileEntry, time: TimeField, config: &Config) -> String {
+    get_file_date(entry, time, config).unwrap_or_else(|| "-".to_string())
+}
+
+/// Get the size as shown in the size column
+/// With dual units this is always the exact number of bytes
+fn get_display_size(entry: &FileEntry, config: &Config) -> String {
+    let unit = match config.dual_units {
+        true => Unit::Bytes,
+        false => config.unit.clone().unwrap_or_default(),
+    };
+    format_display_size(entry, &unit, config)
+}
+
+/// Get the size as shown in the scaled column next to the bytes with --dual-units
+/// That is the unit given with --unit, or the auto unit without one
+fn get_display_scaled_size(entry: &FileEntry, config: &Config) -> String {
+    format_display_size(entry, &get_scaled_unit(config), config)
+}
+
+/// Get the unit of the scaled size with --dual-units, bytes twice would tell nothing new
+fn get_scaled_unit(config: &Config) -> Unit {
+    match &config.unit {
+        Some(Unit::Bytes) | None => Unit::Auto,
+        Some(unit) => unit.clone(),
+    }
+}
+
+/// Format a size in a unit for one of the size columns
+fn format_display_size(entry: &FileEntry, unit: &Unit, config: &Config) -> String {
+    // with aligned sizes the number and the suffix get their own sub-columns
+    match get_file_size_parts(entry, unit, config) {
+        Some((number, suffix)) if config.align_sizes => {
+            let suffix_width = if config.long_units { 10 } else { 4 };
+            format!("{:>8} {:<suffix_width$}", number, suffix)
+        }
+        Some((number, suffix)) => format!("{} {}", number, suffix),
+        None => "-".to_string(),
+    }
+}
+
+/// Get the child count as shown in the child count column
+/// Examples:
+/// directory with 1 entry -> "1 item"
+/// file -> "-"
+fn get_display_child_count(entry: &FileEntry, config: &Config) -> String {
+    get_child_count(&entry.path, config.show_hidden)
+        .map(|count| format!("{} {}", count, if count == 1 { "item" } else { "items" }))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Get the extended attribute count as shown in the xattr column, blank when there are none
+fn get_display_xattrs(entry: &FileEntry) -> String {
+    get_xattr_count(&entry.path)
+        .map(|count| format!("@{}", count))
+        .unwrap_or_default()
+}
+
+/// Get the share of the parent directory as shown in the percent column
+/// Examples:
+/// 42.4 -> "42%"
+/// archive member -> "-"
+fn get_display_percent(entry: &FileEntry) -> String {
+    entry
+        .percent
+        .map(|percent| format!("{:.0}%", percent))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Get the time between creation and last modification as shown in the drift column
+/// A drift of a day or more is highlighted, a negative drift usually means the
+/// file was copied with its modification time preserved
+/// Examples:
+/// modified 2 days after creation -> "+2d"
+/// modified 3 hours before creation -> "-3h"
+/// no creation time -> "-"
+fn get_display_drift(entry: &FileEntry) -> ColoredString {
+    let (Some(created), Some(modified)) = (entry.created, entry.modified) else {
+        return "-".normal();
+    };
+
+    let (secs, sign) = match modified.duration_since(created) {
+        Ok(drift) => (drift.as_secs(), "+"),
+        Err(e) => (e.duration().as_secs(), "-"),
+    };
+    let drift = format!("{}{}", sign, format_short_duration(secs));
+
+    if sign == "+" && secs >= 86400 {
+        drift.yellow()
+    } else {
+        drift.normal()
+    }
+}
+
+/// Get the age bucket of an entry as shown in the age column, by the time since
+/// its last modification. Newer buckets get warmer colors
+/// Examples:
+/// modified 3 hours ago -> "today"
+/// modified 3 days ago -> "this week"
+/// modified 2 years ago -> "older"
+/// no modification time -> "-"
+fn get_display_age_bucket(entry: &FileEntry) -> ColoredString {
+    let Some(modified) = entry.modified else {
+        return "-".normal();
+    };
+
+    // a modification time in the future counts as today
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .map(|age| age.as_secs())
+        .unwrap_or(0);
+
+    match age {
+        0..86400 => "today".green().bold(),
+        86400..604800 => "this week".green(),
+        604800..2592000 => "this month".yellow(),
+        2592000..31536000 => "this year".normal(),
+        _ => "older".dimmed(),
+    }
+}
+
+/// Format a number of seconds in its largest whole unit
+/// Examples:
+/// 42 -> "42s"
+/// 7200 -> "2h"
+/// 200000 -> "2d"
+/// 40000000 -> "1y"
+fn format_short_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        86400..31536000 => format!("{}d", secs / 86400),
+        _ => format!("{}y", secs / 31536000),
+    }
+}
+
+/// Get the allocated size of sparse files as shown in the sparse column, marked
+/// with an S so they stand out. Other entries are left blank
+/// Examples:
+/// sparse 10 gib file with 1 mib allocated -> "S 1.0 mib"
+/// regular file -> ""
+fn get_display_sparse(entry: &FileEntry, config: &Config) -> ColoredString {
+    match entry.allocated {
+        Some(allocated) if is_sparse(entry) => {
+            let unit = config.unit.clone().unwrap_or_default();
+            let (size, suffix) = format_size_parts(allocated, &unit, config);
+            format!("S {} {}", size, suffix).cyan()
+        }
+        _ => "".normal(),
+    }
+}
+
+/// Get the owner and group as shown in the owner column
+/// Examples:
+/// owned by root -> "root root"
+/// owned by root, with numeric_ids -> "0 0"
+fn get_display_owner(entry: &FileEntry, config: &Config) -> String {
+    file_owner(&entry.path, config.numeric_ids).unwrap_or_else(|| "-".to_string())
+}
+
+/// Get the start of the hash, plenty to tell files apart by eye
+fn get_display_hash(entry: &FileEntry) -> &str {
+    entry
+        .hash
+        .as_deref()
+        .map(|hash| &hash[..HASH_WIDTH])
+        .unwrap_or("-")
+}
+
+/// Render entries as a table with box drawing borders and a header row
+/// Column widths fit the widest cell, colors inside the cells are kept
+fn render_boxed_table(entries: &[FileEntry], config: &Config) -> String {
+    let columns = get_columns(config);
+    let headers: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+    let right_aligned: Vec<bool> = columns
+        .iter()
+        .map(|column| column.right_aligned())
+        .collect();
+
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            columns
+                .iter()
+                .map(|column| get_cell(entry, *column, None, config))
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .map(|row| visible_len(&row[i]))
+                .chain([header.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}\n", left, lines.join(middle), right)
+    };
+
+    // pad on the visible width, the escapes in a cell don't take up any room
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let padding = " ".repeat(widths[i].saturating_sub(visible_len(&cell)));
+                if right_aligned[i] {
+                    format!(" {}{} ", padding, cell)
+                } else {
+                    format!(" {}{} ", cell, padding)
+                }
+            })
+            .collect();
+        format!("│{}│\n", cells.join("│"))
+    };
+
+    let mut table = border("┌", "┬", "┐");
+    table.push_str(&line(
+        headers.iter().map(|h| h.bold().to_string()).collect(),
+    ));
+    table.push_str(&border("├", "┼", "┤"));
+    for row in rows {
+        table.push_str(&line(row));
+    }
+    table.push_str(&border("└", "┴", "┘"));
+
+    table
+}
+
+const DATE_WIDTH: usize = 20;
+const PERM_WIDTH: usize = 12;
+const EXT_WIDTH: usize = 5;
+const ENCODING_WIDTH: usize = 12;
+const CHILD_COUNT_WIDTH: usize = 10;
+const XATTR_WIDTH: usize = 4;
+const FLAGS_WIDTH: usize = 6;
+const OWNER_WIDTH: usize = 18;
+const PERCENT_WIDTH: usize = 4;
+const DRIFT_WIDTH: usize = 6;
+const AGE_BUCKET_WIDTH: usize = 10;
+const SPARSE_WIDTH: usize = 12;
+const HASH_WIDTH: usize = 16;
+
+/// Width of the date column
+/// Full ISO dates don't fit the default width, so the column is widened for them
+fn date_column_width(config: &Config) -> usize {
+    match config.time_style {
+        TimeStyle::FullIso => 36,
+        _ => DATE_WIDTH,
+    }
+}
+
+/// Width of the size column
+/// Spelled out units are longer, so the column is widened to stay aligned
+/// Exact byte counts with --dual-units take more room, so the column is widened for them
+fn size_column_width(config: &Config) -> usize {
+    let width = scaled_size_width(config);
+    if config.dual_units { width + 4 } else { width }
+}
+
+/// Width of a size in a unit, the size column or the scaled column with --dual-units
+fn scaled_size_width(config: &Config) -> usize {
+    match (config.long_units, config.align_sizes) {
+        (true, true) => 19,
+        (false, true) => 13,
+        (true, false) => 18,
+        (false, false) => 10,
+    }
+}
+
+/// Get the name column width that makes a row fill the terminal
+/// The name column never gets narrower than the default of 26
+/// Examples:
+/// terminal of 80 columns with the default columns -> 28
+/// terminal of 60 columns -> 26
+pub fn fit_name_width(config: &Config, terminal_width: usize) -> usize {
+    let others: usize = get_columns(config)
+        .into_iter()
+        .filter(|column| *column != Column::Name)
+        .map(|column| column.width(config) + 1)
+        .sum();
+
+    terminal_width.saturating_sub(others + 1).max(26)
+}
+
+/// Render entries grouped by their parent directory, like `ls -R`
+/// Each group gets a bold directory header and is separated by a blank line
+fn render_grouped(groups: Vec<(PathBuf, Vec<FileEntry>)>, config: &Config) -> String {
+    let mut output = String::new();
+
+    for (i, (dir, entries)) in groups.into_iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+
+        output.push_str(&format!("{}\n", format!("{}:", dir.display()).bold()));
+
+        let total = entries.len();
+        let limit = config.per_dir_limit.unwrap_or(total);
+        output.push_str(&render_as_table(
+            entries.into_iter().take(limit).collect(),
+            config,
+        ));
+        if total > limit {
+            let more = format!("… and {} more", total - limit);
+            output.push_str(&format!("{}\n", more.dimmed()));
+        }
+    }
+
+    output.trim_end().to_string()
 }
 
 /// Render multiple file entries as a formatted table
 /// Experimental function as this stinks a lil
-fn render_as_table(entries: Vec<walkdir::DirEntry>, canonicalize: bool, unit: &Unit) -> String {
+fn render_as_table(entries: Vec<FileEntry>, config: &Config) -> String {
     let mut table = String::new();
 
     for entry in entries {
-        let row = render_as_row(entry, canonicalize, unit);
+        let row = render_as_row(&entry, config);
         table.push_str(&row);
         table.push('\n');
     }
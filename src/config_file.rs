@@ -0,0 +1,191 @@
+#[cfg(feature = "cli")]
+use crate::cli::Args;
+use crate::config::{ColorMode, Config, Size, SortKey};
+
+use serde::{Deserialize, Deserializer, de};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Contents of the fview config file
+/// Lives at `$XDG_CONFIG_HOME/fview/config.toml` or `~/.config/fview/config.toml`
+///
+/// Example:
+/// [profiles.code]
+/// show_hidden = true
+/// max_depth = 3
+/// skip_larger_than = "10mb"
+/// sort = "modified"
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// A named set of options that can be loaded with `--profile NAME`
+/// Every field is optional, unset fields leave the config untouched
+/// The same set of options can be given in FVIEW_* environment variables, and
+/// the options given on the command line are one more layer of them
+#[derive(Debug, Default, Deserialize)]
+pub struct Profile {
+    pub max_depth: Option<usize>,
+    pub canonicalize: Option<bool>,
+    pub show_hidden: Option<bool>,
+    pub table: Option<bool>,
+    pub reversed: Option<bool>,
+    pub group_by_dir: Option<bool>,
+    #[serde(default, deserialize_with = "parse_field")]
+    pub skip_larger_than: Option<Size>,
+    #[serde(default, deserialize_with = "parse_field")]
+    pub sort: Option<SortKey>,
+    #[serde(default, deserialize_with = "parse_field")]
+    pub color: Option<ColorMode>,
+}
+
+impl Profile {
+    /// Read the options from FVIEW_* environment variables, named after the fields
+    /// Flags take 1/0, true/false or yes/no, unknown variables are ignored
+    /// Examples:
+    /// FVIEW_SORT=size -> sort: Some(SortKey::Size)
+    /// FVIEW_SHOW_HIDDEN=1 -> show_hidden: Some(true)
+    /// FVIEW_MAX_DEPTH=deep -> Err("Invalid value for FVIEW_MAX_DEPTH: deep")
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self, String> {
+        let mut profile = Profile::default();
+
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix("FVIEW_") else {
+                continue;
+            };
+            let invalid = || format!("Invalid value for {}: {}", name, value);
+            let flag = || parse_env_flag(&value).ok_or_else(invalid);
+
+            match key {
+                "MAX_DEPTH" => profile.max_depth = Some(value.parse().map_err(|_| invalid())?),
+                "CANONICALIZE" => profile.canonicalize = Some(flag()?),
+                "SHOW_HIDDEN" => profile.show_hidden = Some(flag()?),
+                "TABLE" => profile.table = Some(flag()?),
+                "REVERSED" => profile.reversed = Some(flag()?),
+                "GROUP_BY_DIR" => profile.group_by_dir = Some(flag()?),
+                "SKIP_LARGER_THAN" => profile.skip_larger_than = Some(value.parse()?),
+                "SORT" => profile.sort = Some(value.parse()?),
+                "COLOR" => profile.color = Some(value.parse()?),
+                _ => {}
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// Apply the profile on top of a config, every option the profile sets
+    /// replaces the one in the config. Applying the sources from the lowest
+    /// precedence to the highest leaves the config with the value of the
+    /// strongest source that set each option
+    pub fn apply(&self, config: &mut Config) {
+        set(&mut config.max_depth, self.max_depth.map(Some));
+        set(&mut config.canonicalize, self.canonicalize);
+        set(&mut config.show_hidden, self.show_hidden);
+        set(&mut config.table, self.table);
+        set(&mut config.reversed, self.reversed);
+        set(&mut config.group_by_dir, self.group_by_dir);
+        set(
+            &mut config.skip_larger_than,
+            self.skip_larger_than
+                .as_ref()
+                .map(|size| Some(size.bytes(config.si))),
+        );
+        set(&mut config.sort, self.sort.map(Some));
+        set(&mut config.color, self.color);
+    }
+}
+
+/// The options given on the command line as a layer, flags that weren't given
+/// are unset instead of false, so they don't override the other sources
+#[cfg(feature = "cli")]
+impl From<&Args> for Profile {
+    fn from(args: &Args) -> Self {
+        let flag = |given: bool| given.then_some(true);
+
+        Profile {
+            max_depth: args.max_depth,
+            canonicalize: flag(args.canonicalize),
+            show_hidden: flag(args.show_hidden),
+            table: flag(args.table),
+            reversed: flag(args.reversed),
+            group_by_dir: flag(args.group_by_dir || args.pretty),
+            skip_larger_than: args.skip_larger_than.clone(),
+            sort: args.sort.first().map(|&(key, _)| key),
+            color: args.color,
+        }
+    }
+}
+
+/// Overwrite an option with a value, if there is one
+fn set<T>(option: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *option = value;
+    }
+}
+
+/// Deserialize an option from its string form, the same one the command line takes
+/// Example:
+/// sort = "modified" -> Some(SortKey::Modified)
+fn parse_field<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Parse the value of a flag environment variable
+/// Examples:
+/// "1" -> Some(true)
+/// "no" -> Some(false)
+/// "maybe" -> None
+fn parse_env_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Get the fview config directory
+/// Returns None if neither XDG_CONFIG_HOME nor HOME is set
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("fview"))
+}
+
+/// Get the path of the config file
+pub fn config_file_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Load and parse the config file
+pub fn load_config_file() -> Result<ConfigFile, String> {
+    let path = config_file_path().ok_or("Could not determine the config directory")?;
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+
+    toml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+}
+
+/// Load a named profile from the config file
+/// An unknown profile name is an error
+pub fn load_profile(name: &str) -> Result<Profile, String> {
+    let mut config_file = load_config_file()?;
+
+    config_file
+        .profiles
+        .remove(name)
+        .ok_or_else(|| format!("Unknown profile: {}", name))
+}
@@ -0,0 +1,98 @@
+use crate::config::{EntryKind, FileEntry};
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How an entry differs between two listings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    /// Only in the listed directory
+    Added,
+    /// Only in the other directory
+    Removed,
+    /// In both, but with a different size or modification time
+    Changed,
+}
+
+/// A single difference between two listings
+#[derive(Debug, Clone)]
+pub struct DiffEntry {
+    pub change: Change,
+    /// Path relative to the root of its listing
+    pub relative_path: PathBuf,
+    /// The entry from the listed directory, or the other directory when removed
+    pub entry: FileEntry,
+    /// The entry from the other directory, only set when changed
+    pub previous: Option<FileEntry>,
+}
+
+/// Compare two listings by relative path
+/// `base` is the listing of the other directory, `target` the listing of the
+/// directory being viewed. Directories are only reported when added or removed,
+/// their size and mtime change whenever their contents do
+/// The result is ordered by relative path
+pub fn diff_listings(
+    base: Vec<FileEntry>,
+    base_root: &Path,
+    target: Vec<FileEntry>,
+    target_root: &Path,
+) -> Vec<DiffEntry> {
+    let mut base = key_by_relative_path(base, base_root);
+    let target = key_by_relative_path(target, target_root);
+
+    let mut diff = Vec::new();
+
+    for (relative_path, entry) in target {
+        match base.remove(&relative_path) {
+            None => diff.push(DiffEntry {
+                change: Change::Added,
+                relative_path,
+                entry,
+                previous: None,
+            }),
+            Some(previous) if is_changed(&previous, &entry) => diff.push(DiffEntry {
+                change: Change::Changed,
+                relative_path,
+                entry,
+                previous: Some(previous),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for (relative_path, entry) in base {
+        diff.push(DiffEntry {
+            change: Change::Removed,
+            relative_path,
+            entry,
+            previous: None,
+        });
+    }
+
+    diff.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    diff
+}
+
+fn key_by_relative_path(entries: Vec<FileEntry>, root: &Path) -> BTreeMap<PathBuf, FileEntry> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let relative = entry
+                .path
+                .strip_prefix(root)
+                .unwrap_or(&entry.path)
+                .to_path_buf();
+            (relative, entry)
+        })
+        .collect()
+}
+
+fn is_changed(previous: &FileEntry, current: &FileEntry) -> bool {
+    if previous.kind == EntryKind::Dir && current.kind == EntryKind::Dir {
+        return false;
+    }
+
+    previous.kind != current.kind
+        || previous.size != current.size
+        || previous.modified != current.modified
+}
@@ -0,0 +1,123 @@
+use crate::log;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Compute the total size in bytes of everything inside a directory
+/// Symlinks inside it count with their own size and are never followed, like
+/// `du`, so a link to `/` doesn't add up the whole filesystem. Only the
+/// directory itself is resolved when it is a link, as that is what was asked for
+/// Every directory is tracked by its (device, inode) pair and visited at most
+/// once, so a bind mount or hardlinked directory looping back to an ancestor is
+/// skipped instead of walked forever
+/// If verbose is true, skipped cycles are reported on stderr
+/// With a max depth, only files up to that many levels below the directory
+/// are counted. Deeper directories are skipped and the total is marked as
+/// truncated, so it is a lower bound of the real size
+/// Directories that can't be read, e.g. without permission, are skipped and
+/// counted, also making the total a lower bound. In verbose mode they are
+/// reported on stderr
+/// With a cap, counting stops as soon as the total goes over it and the total
+/// is marked as capped. Which files got counted by then depends on the
+/// directory order, so a capped total is only known to be more than the cap
+pub fn dir_size(path: &Path, verbose: bool, max_depth: Option<usize>, cap: Option<u64>) -> DirSize {
+    let mut visited = HashSet::new();
+    let mut size = DirSize::default();
+    dir_size_inner(path, verbose, max_depth, cap, &mut visited, &mut size);
+    size
+}
+
+/// Total size of a directory's contents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirSize {
+    pub bytes: u64,
+    /// True when the max depth cut off part of the tree
+    pub truncated: bool,
+    /// Number of directories and entries that couldn't be read
+    pub unreadable: usize,
+    /// True when counting stopped because the total went over the cap
+    pub capped: bool,
+}
+
+impl DirSize {
+    /// Check if part of the tree is missing from the total, so it is only a lower bound
+    pub fn is_partial(&self) -> bool {
+        self.truncated || self.unreadable > 0 || self.capped
+    }
+}
+
+fn dir_size_inner(
+    path: &Path,
+    verbose: bool,
+    depth_left: Option<usize>,
+    cap: Option<u64>,
+    visited: &mut HashSet<(u64, u64)>,
+    size: &mut DirSize,
+) {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return skip_unreadable(path, e, verbose, size),
+    };
+
+    if !visited.insert((metadata.dev(), metadata.ino())) {
+        if verbose {
+            log::warn(format!(
+                "Skipping already visited directory: {}",
+                path.display()
+            ));
+        }
+        return;
+    }
+
+    if depth_left == Some(0) {
+        size.truncated = true;
+        return;
+    }
+
+    let read_dir = match fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(e) => return skip_unreadable(path, e, verbose, size),
+    };
+
+    for child in read_dir {
+        if size.capped {
+            return;
+        }
+
+        let child_path = match child {
+            Ok(child) => child.path(),
+            Err(e) => {
+                skip_unreadable(path, e, verbose, size);
+                continue;
+            }
+        };
+        // a symlink is not a directory here, so it is counted instead of followed
+        let child_metadata = match fs::symlink_metadata(&child_path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                skip_unreadable(&child_path, e, verbose, size);
+                continue;
+            }
+        };
+
+        if child_metadata.is_dir() {
+            let depth_left = depth_left.map(|depth| depth - 1);
+            dir_size_inner(&child_path, verbose, depth_left, cap, visited, size);
+        } else {
+            size.bytes += child_metadata.len();
+            size.capped = cap.is_some_and(|cap| size.bytes > cap);
+        }
+    }
+}
+
+/// Count a directory or entry that couldn't be read, which makes the total a lower bound
+/// In verbose mode the path is reported on stderr
+fn skip_unreadable(path: &Path, e: io::Error, verbose: bool, size: &mut DirSize) {
+    if verbose {
+        log::warn(format!("Cannot read {}: {}", path.display(), e));
+    }
+    size.unreadable += 1;
+}
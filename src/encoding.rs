@@ -0,0 +1,59 @@
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::Encoding;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How many bytes from the start of a file are used to guess its encoding
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// Detect the character encoding of a text file from its first few KiB
+/// Returns None for binary files and files that can't be read
+/// Examples:
+/// plain ascii or utf-8 text -> Some("UTF-8")
+/// text with a UTF-16LE BOM -> Some("UTF-16LE")
+/// latin-1 text -> Some("windows-1252")
+/// an executable -> None
+pub fn detect_encoding(path: &Path) -> Option<String> {
+    let mut buf = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(SNIFF_LEN)
+        .read_to_end(&mut buf)
+        .ok()?;
+
+    if buf.is_empty() {
+        return None;
+    }
+
+    if let Some((encoding, _)) = Encoding::for_bom(&buf) {
+        return Some(encoding.name().to_string());
+    }
+
+    // NUL bytes don't show up in text without a BOM, so call it binary
+    if buf.contains(&0) {
+        return None;
+    }
+
+    if is_utf8_prefix(&buf) {
+        return Some("UTF-8".to_string());
+    }
+
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+    detector.feed(&buf, false);
+    Some(
+        detector
+            .guess(None, Utf8Detection::Allow)
+            .name()
+            .to_string(),
+    )
+}
+
+/// Check if the bytes are valid UTF-8, allowing a multi-byte sequence to be
+/// cut off at the end because we only read the start of the file
+fn is_utf8_prefix(buf: &[u8]) -> bool {
+    match std::str::from_utf8(buf) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
@@ -0,0 +1,161 @@
+use crate::log;
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Fallback for ARG_MAX when sysconf can't tell, the smallest POSIX allows
+const MIN_ARG_MAX: usize = 4096;
+
+/// Linux refuses any single argument longer than this, MAX_ARG_STRLEN
+const MAX_ARG_LEN: usize = 128 * 1024;
+
+/// A command to run over the listed entries, parsed from --exec
+/// Examples:
+/// "wc -l {}" -> runs `wc -l <path>` once per entry
+/// "wc -l {} +" -> runs `wc -l <path> <path> ...` once for all entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecCommand {
+    program: String,
+    args: Vec<String>,
+    batch: bool,
+}
+
+impl ExecCommand {
+    /// Parse a command line, split on whitespace
+    /// Quotes and backslashes are taken literally, so an argument can't contain spaces
+    /// `{}` is replaced by the path, a trailing `+` passes all paths to one invocation
+    /// Without a `{}` the path is appended as the last argument
+    pub fn parse(command: &str) -> Result<Self, String> {
+        let mut words: Vec<String> = command.split_whitespace().map(String::from).collect();
+
+        let batch = words.last().is_some_and(|word| word == "+");
+        if batch {
+            words.pop();
+        }
+
+        if words.is_empty() {
+            return Err(format!("Invalid exec command: {}", command));
+        }
+
+        if !words.iter().any(|word| word.contains("{}")) {
+            words.push("{}".to_string());
+        }
+
+        let program = words.remove(0);
+        Ok(ExecCommand {
+            program,
+            args: words,
+            batch,
+        })
+    }
+
+    /// Run the command over the given paths
+    /// In batch mode the paths are split over as many invocations as it takes to
+    /// stay under the system's argument size limit, like xargs
+    /// Returns the number of invocations that failed or could not be started
+    pub fn run(&self, paths: &[PathBuf]) -> usize {
+        if paths.is_empty() {
+            return 0;
+        }
+
+        if self.batch {
+            return batches(paths, self.batch_budget())
+                .into_iter()
+                .filter(|batch| self.spawn(batch))
+                .count();
+        }
+
+        paths
+            .iter()
+            .filter(|path| self.spawn(std::slice::from_ref(*path)))
+            .count()
+    }
+
+    /// Bytes the paths of one batch invocation may take up
+    /// ARG_MAX covers the arguments and the environment together, so the
+    /// environment and the rest of the command come off it, and half of what is
+    /// left is used to stay clear of anything else the kernel counts. Paths joined
+    /// into a single argument, like "{}.bak", must also fit in one argument
+    fn batch_budget(&self) -> usize {
+        // SAFETY: sysconf only reads a system limit
+        let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+        let arg_max = usize::try_from(arg_max).unwrap_or(0).max(MIN_ARG_MAX);
+
+        let env: usize = env::vars_os()
+            .map(|(name, value)| arg_size(name.len() + value.len() + 1))
+            .sum();
+        let command: usize = [&self.program]
+            .into_iter()
+            .chain(&self.args)
+            .map(|arg| arg_size(arg.len()))
+            .sum();
+
+        let budget = arg_max.saturating_sub(env + command) / 2;
+        match self
+            .args
+            .iter()
+            .any(|arg| arg != "{}" && arg.contains("{}"))
+        {
+            true => budget.min(MAX_ARG_LEN / 2),
+            false => budget,
+        }
+    }
+
+    /// Run a single invocation, returns true if it failed
+    fn spawn(&self, paths: &[PathBuf]) -> bool {
+        let mut command = Command::new(&self.program);
+
+        for arg in &self.args {
+            if arg == "{}" {
+                command.args(paths);
+            } else if arg.contains("{}") {
+                let joined = paths
+                    .iter()
+                    .map(|path| path.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                command.arg(arg.replace("{}", &joined));
+            } else {
+                command.arg(arg);
+            }
+        }
+
+        match command.status() {
+            Ok(status) => !status.success(),
+            Err(e) => {
+                log::warn(format!("Failed to run {}: {}", self.program, e));
+                true
+            }
+        }
+    }
+}
+
+/// Split paths into batches that each take up at most budget bytes as arguments
+/// A path over the budget on its own still gets a batch, the command then reports
+/// the error for it
+/// Example, with a budget for two of these paths:
+/// ["a", "b", "c"] -> [["a", "b"], ["c"]]
+pub fn batches(paths: &[PathBuf], budget: usize) -> Vec<&[PathBuf]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (i, path) in paths.iter().enumerate() {
+        let path_size = arg_size(path.as_os_str().len());
+        if i > start && size + path_size > budget {
+            batches.push(&paths[start..i]);
+            start = i;
+            size = 0;
+        }
+        size += path_size;
+    }
+    batches.push(&paths[start..]);
+
+    batches
+}
+
+/// Bytes an argument of len bytes takes up: the string, its nul and its pointer
+fn arg_size(len: usize) -> usize {
+    len + 1 + size_of::<usize>()
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+/// Flags in the order lsattr prints them, with their letter
+#[cfg(target_os = "linux")]
+const FLAGS: [(libc::c_long, char); 21] = [
+    (0x0000_0001, 's'), // secure deletion
+    (0x0000_0002, 'u'), // undeletable
+    (0x0000_0008, 'S'), // synchronous updates
+    (0x0001_0000, 'D'), // synchronous directory updates
+    (0x0000_0010, 'i'), // immutable
+    (0x0000_0020, 'a'), // append only
+    (0x0000_0040, 'd'), // no dump
+    (0x0000_0080, 'A'), // no atime updates
+    (0x0000_0004, 'c'), // compressed
+    (0x0000_0800, 'E'), // encrypted
+    (0x0000_4000, 'j'), // data journaling
+    (0x0000_1000, 'I'), // indexed directory
+    (0x0000_8000, 't'), // no tail merging
+    (0x0002_0000, 'T'), // top of directory hierarchy
+    (0x0008_0000, 'e'), // extents
+    (0x0080_0000, 'C'), // no copy on write
+    (0x0200_0000, 'x'), // direct access
+    (0x4000_0000, 'F'), // casefolded
+    (0x1000_0000, 'N'), // inline data
+    (0x2000_0000, 'P'), // project hierarchy
+    (0x0010_0000, 'V'), // verity
+];
+
+/// Get the lsattr style flags of a file, only the letters of flags that are set
+/// Symlinks, and files on filesystems without flags, return None
+/// Examples:
+/// immutable file on ext4 -> Some("ie")
+/// plain file on ext4 -> Some("e")
+#[cfg(target_os = "linux")]
+pub fn file_flags(path: &Path) -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    if path.symlink_metadata().ok()?.file_type().is_symlink() {
+        return None;
+    }
+
+    // lsattr opens with O_NONBLOCK too, so fifos and devices don't hang
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .ok()?;
+
+    let mut flags: libc::c_long = 0;
+    // SAFETY: the fd is valid for the lifetime of file and flags outlives the call
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) };
+    if result != 0 {
+        return None;
+    }
+
+    let letters = FLAGS
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, letter)| letter)
+        .collect();
+
+    Some(letters)
+}
+
+/// File flags are a Linux thing, everywhere else there is nothing to show
+#[cfg(not(target_os = "linux"))]
+pub fn file_flags(_path: &Path) -> Option<String> {
+    None
+}
@@ -0,0 +1,37 @@
+use git2::{DiffOptions, Repository};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Get the files that changed between a commit and the working tree
+/// `dir` can be anywhere inside the repository, `reference` is anything git
+/// can resolve to a commit (a hash, branch or tag). Untracked files count as changed
+/// The returned paths are absolute and canonicalized
+pub fn changed_since(dir: &Path, reference: &str) -> Result<HashSet<PathBuf>, String> {
+    let repo = Repository::discover(dir)
+        .map_err(|_| format!("Not inside a git repository: {}", dir.display()))?;
+    let workdir = repo
+        .workdir()
+        .ok_or("The git repository has no working tree")?;
+    let workdir = workdir.canonicalize().unwrap_or(workdir.to_path_buf());
+
+    let tree = repo
+        .revparse_single(reference)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| format!("Unknown git ref {}: {}", reference, e.message()))?;
+
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+
+    let diff = repo
+        .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+        .map_err(|e| format!("Failed to diff against {}: {}", reference, e.message()))?;
+
+    let changed = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(|path| workdir.join(path))
+        .collect();
+
+    Ok(changed)
+}
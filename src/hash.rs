@@ -0,0 +1,59 @@
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Get the SHA-256 of a file's contents as lowercase hex
+/// Files that can't be read return None
+pub fn hash_file(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Some(hash)
+}
+
+/// Hash many files at once on a pool of `jobs` threads
+/// Hashing is mostly waiting on the disk, so a few threads go a long way,
+/// but too many make spinning disks seek back and forth
+/// Files that can't be read are left out of the result
+pub fn hash_files(paths: &[PathBuf], jobs: usize) -> HashMap<PathBuf, String> {
+    let hash_all = || {
+        paths
+            .par_iter()
+            .filter_map(|path| Some((path.clone(), hash_file(path)?)))
+            .collect()
+    };
+
+    match rayon::ThreadPoolBuilder::new().num_threads(jobs).build() {
+        Ok(pool) => pool.install(hash_all),
+        // no pool means no threads to spare, hash on this one instead
+        Err(_) => paths
+            .iter()
+            .filter_map(|path| Some((path.clone(), hash_file(path)?)))
+            .collect(),
+    }
+}
+
+/// Default number of hashing threads, one per CPU
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
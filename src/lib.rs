@@ -0,0 +1,54 @@
+//! fview can be used as a library as well as a binary
+//!
+//! `collect_entries` walks a directory and returns the data for every entry,
+//! without printing anything:
+//!
+//! ```
+//! use fview::config::{Config, collect_entries};
+//!
+//! let config = Config {
+//!     dir: "./src".to_string(),
+//!     ..Default::default()
+//! };
+//!
+//! for entry in collect_entries(&config) {
+//!     println!("{} {:?} {:?}", entry.name, entry.kind, entry.size);
+//! }
+//! ```
+//!
+//! `view_files` renders the listing into any `std::io::Write`, so the output
+//! can be captured in a buffer instead of going to stdout:
+//!
+//! ```
+//! use fview::config::{Config, view_files};
+//!
+//! let mut output = Vec::new();
+//! view_files(Some(Config::default()), &mut output).unwrap();
+//! ```
+//!
+//! The command line parsing and the binary sit behind the default `cli` feature,
+//! so a library only dependency doesn't pull in clap:
+//!
+//! ```toml
+//! fview = { version = "0.1", default-features = false }
+//! ```
+pub mod archive;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod config;
+pub mod config_file;
+pub mod diff;
+pub mod dir_size;
+pub mod encoding;
+pub mod exec;
+pub mod file_flags;
+pub mod git;
+pub mod hash;
+pub mod log;
+pub mod mounts;
+pub mod owner;
+pub mod string_ext;
+pub mod template;
+pub mod theme;
+pub mod trash;
+pub mod verify;
@@ -0,0 +1,17 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Silence warnings for the rest of the run, fatal errors still get printed
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Print a non-fatal warning on stderr, unless warnings are silenced
+/// Anything that stops fview should use eprintln directly instead
+pub fn warn(message: impl Display) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    }
+}
@@ -1,28 +1,236 @@
-use clap::Parser;
-pub mod cli;
-pub mod config;
-pub mod string_ext;
-
-use crate::{cli::Args, config::Config};
+use clap::{CommandFactory, Parser};
+use flate2::write::GzEncoder;
+use fview::{
+    cli::Args,
+    config,
+    config::{ColorMode, Compression},
+    git, hash, log, verify,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use terminal_size::Width;
 
 fn main() {
     let cli = Args::parse();
 
-    let mut config = Config::from(cli);
+    if let Some(shell) = cli.generate_completions {
+        clap_complete::generate(shell, &mut Args::command(), "fview", &mut io::stdout());
+        return;
+    }
+
+    let output = cli.output.clone();
+    let no_clobber = cli.no_clobber;
+    let compress = cli.compress;
+    let since_commit = cli.since_commit.clone();
+    let exec = cli.exec.clone();
+    let exec_only = cli.exec_only;
+    let pretty = cli.pretty;
+    let from_stdin = cli.from_stdin || cli.stdin_null;
+    let stdin_null = cli.stdin_null;
+    let verify = cli.verify.clone();
+    let interval = cli.interval.map(Duration::from_secs);
+    log::set_quiet(cli.quiet);
+
+    let mut config = match config::resolve_config(cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // If the default directory is still "./", use the actual current working directory
-    if config.dir == "./" {
-        match std::env::current_dir() {
-            Ok(cwd) => {
-                config.dir = cwd.to_string_lossy().into_owned();
+    if from_stdin {
+        match config::read_stdin_paths(io::stdin().lock(), stdin_null) {
+            Ok(paths) => config.paths = Some(paths),
+            Err(e) => {
+                eprintln!("Error reading paths from stdin: {}", e);
+                std::process::exit(1);
             }
+        }
+    }
+
+    if let Some(reference) = since_commit {
+        match git::changed_since(Path::new(&config.dir), &reference) {
+            Ok(changed) => config.changed_paths = Some(changed),
             Err(e) => {
-                eprintln!("Error getting current directory: {}", e);
-                // Fallback to "./" if we can't get the current directory
-                config.dir = "./".to_string();
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Escape codes are just noise in a file, unless explicitly asked for
+    if output.is_some() && config.color != ColorMode::Always {
+        config.color = ColorMode::Never;
+        config.no_icons = true;
+    }
+
+    config.color.apply();
+
+    if output.is_none()
+        && let Some((Width(width), _)) = terminal_size::terminal_size()
+    {
+        config.terminal_width = Some(width as usize);
+        if pretty {
+            config.name_width = Some(config::fit_name_width(&config, width as usize));
+        }
+    }
+
+    // Buffered, so a big listing isn't written out one line per syscall
+    // The buffer is flushed once after the listing, before any --exec command runs
+    let mut out = BufWriter::new(match output {
+        Some(path) => {
+            let compress = compress.or_else(|| Compression::from_path(Path::new(&path)));
+            match open_output(&path, no_clobber, compress) {
+                Ok(output) => output,
+                Err(e) => {
+                    eprintln!("Error opening output file {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Output::Stdout(io::stdout().lock()),
+    });
+
+    if let Some(sums_file) = verify {
+        let sums = verify::read_sums_file(&sums_file).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
+        let jobs = config.jobs.unwrap_or_else(hash::default_jobs);
+        let report = verify::verify(&sums, Path::new(&config.dir), jobs, &mut out)
+            .and_then(|report| finish_output(out).map(|_| report))
+            .unwrap_or_else(|e| exit_on_write_error(e));
+
+        if report.is_ok() {
+            return;
+        }
+        if report.failed > 0 {
+            eprintln!("{} computed checksum(s) did NOT match", report.failed);
+        }
+        if report.missing > 0 {
+            eprintln!("{} listed file(s) could not be read", report.missing);
+        }
+        std::process::exit(1);
+    }
+
+    if let Some(interval) = interval {
+        loop {
+            // clear the screen and move the cursor to the top left before redrawing
+            let drawn = write!(out, "\x1b[2J\x1b[H")
+                .and_then(|_| config::view_files(Some(config.clone()), &mut out))
+                .and_then(|_| out.flush());
+            if let Err(e) = drawn {
+                exit_on_write_error(e);
             }
+            thread::sleep(interval);
         }
     }
 
-    config::view_files(Some(config));
+    // With --exec the directory is walked once, and the same entries are listed and run on
+    let entries = exec.as_ref().map(|_| config::collect_entries(&config));
+    let exec_paths = entries
+        .as_deref()
+        .map(config::exec_paths)
+        .unwrap_or_default();
+
+    let lists_entries = config.diff.is_none() && !config.total_only;
+    let written = match entries {
+        _ if exec_only => Ok(()),
+        Some(entries) if lists_entries => config::view_entries(config, entries, &mut out),
+        _ => config::view_files(Some(config), &mut out),
+    };
+    if let Err(e) = written.and_then(|_| finish_output(out)) {
+        exit_on_write_error(e);
+    }
+
+    if let Some(exec) = exec {
+        let failed = exec.run(&exec_paths);
+        if failed > 0 {
+            eprintln!("{} command(s) failed", failed);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Exit after the output couldn't be written
+/// A reader that stops early, like `fview | head`, closes the pipe, which is a
+/// normal way for a listing to end, so that exits quietly and successfully
+/// Any other error is reported and exits with a failure
+fn exit_on_write_error(e: io::Error) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        std::process::exit(0);
+    }
+
+    eprintln!("Error writing output: {}", e);
+    std::process::exit(1);
+}
+
+/// Open the output file, truncating it if it exists
+/// If no_clobber is true, an existing file is an error instead
+/// With a compressor, everything written goes through it
+fn open_output(path: &str, no_clobber: bool, compress: Option<Compression>) -> io::Result<Output> {
+    let file = if no_clobber {
+        OpenOptions::new().write(true).create_new(true).open(path)?
+    } else {
+        File::create(path)?
+    };
+
+    Ok(match compress {
+        None => Output::File(file),
+        Some(Compression::Gzip) => {
+            Output::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+        }
+        Some(Compression::Zstd) => Output::Zstd(zstd::Encoder::new(file, 0)?),
+    })
+}
+
+/// Where the listing is written, stdout or the --output file
+enum Output {
+    Stdout(io::StdoutLock<'static>),
+    File(File),
+    Gzip(GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Output {
+    /// Flush everything written and end a compressed stream
+    /// A compressed file is cut short without its end, so this has to run before exiting
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Output::Stdout(mut stdout) => stdout.flush(),
+            Output::File(mut file) => file.flush(),
+            Output::Gzip(encoder) => encoder.finish().map(drop),
+            Output::Zstd(encoder) => encoder.finish().map(drop),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File(file) => file.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+            Output::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(file) => file.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+            Output::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Write out what is left in the buffer and finish the output
+fn finish_output(out: BufWriter<Output>) -> io::Result<()> {
+    out.into_inner().map_err(|e| e.into_error())?.finish()
 }
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Check if a directory is a mount point, it is on another device than its parent
+/// Symlinks are never mount points, the root has no parent and counts as one
+/// Examples:
+/// "/proc" -> true
+/// "/usr/bin" -> false on a single root filesystem
+pub fn is_mount_point(path: &Path) -> bool {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return false;
+    };
+    if !metadata.is_dir() {
+        return false;
+    }
+
+    let parent = path.join("..");
+    match parent.metadata() {
+        Ok(parent) => parent.dev() != metadata.dev() || parent.ino() == metadata.ino(),
+        Err(_) => false,
+    }
+}
+
+/// Get the filesystem type mounted at a path, e.g. "ext4" or "tmpfs"
+/// Read from /proc/self/mounts, so only known on Linux. The path is resolved
+/// first, so a relative path or one through a symlink still finds its mount
+pub fn mount_fs_type(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    mount_types().get(&path).cloned()
+}
+
+/// Filesystem types by mount point, read once from /proc/self/mounts
+/// When several filesystems are mounted on the same point the last one is on top
+fn mount_types() -> &'static HashMap<PathBuf, String> {
+    static MOUNTS: OnceLock<HashMap<PathBuf, String>> = OnceLock::new();
+    MOUNTS.get_or_init(|| {
+        let contents = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+        contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ');
+                let mount_point = fields.nth(1)?;
+                let fs_type = fields.next()?;
+                Some((PathBuf::from(unescape(mount_point)), fs_type.to_string()))
+            })
+            .collect()
+    })
+}
+
+/// Undo the octal escapes the kernel puts in mount points for spaces and such
+/// Example:
+/// "/mnt/my\040disk" -> "/mnt/my disk"
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = field;
+
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let code = rest.get(index + 1..index + 4);
+        match code.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+
+    unescaped
+}
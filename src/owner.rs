@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+/// Names already looked up, by id. Ids without a name are kept as None so they
+/// aren't looked up again
+type NameCache = OnceLock<Mutex<HashMap<u32, Option<String>>>>;
+
+/// Get the owner and group of a file as "user group", like the `ls -l` columns
+/// Symlinks report their own owner, not the one of their target
+/// With numeric, the raw uid and gid are shown without looking up any names,
+/// ids without a name fall back to the number as well
+/// Names come from the system user database, so NSS users like LDAP ones resolve
+/// Examples:
+/// ("/etc/passwd", false) -> "root root"
+/// ("/etc/passwd", true) -> "0 0"
+pub fn file_owner(path: &Path, numeric: bool) -> Option<String> {
+    let (uid, gid) = file_ids(path)?;
+
+    if numeric {
+        return Some(format!("{} {}", uid, gid));
+    }
+
+    let user = user_name(uid);
+    let group = group_name(gid);
+
+    Some(format!(
+        "{} {}",
+        user.unwrap_or_else(|| uid.to_string()),
+        group.unwrap_or_else(|| gid.to_string())
+    ))
+}
+
+/// Get the uid and gid of a file, symlinks report their own
+pub fn file_ids(path: &Path) -> Option<(u32, u32)> {
+    let metadata = path.symlink_metadata().ok()?;
+    Some((metadata.uid(), metadata.gid()))
+}
+
+/// Parse a user name or numeric uid for --owner
+/// Names are looked up in the system user database, so NSS users resolve and
+/// every name of a uid works, e.g. "toor" next to "root"
+/// With ignore_case, the name may differ from the user name in case
+/// Examples:
+/// ("root", false) -> 0
+/// ("ROOT", true) -> 0
+/// ("1000", false) -> 1000
+/// ("nosuchuser", false) -> Err("Unknown user: nosuchuser")
+pub fn parse_user(s: &str, ignore_case: bool) -> Result<u32, String> {
+    s.parse()
+        .ok()
+        .or_else(|| user_id(s))
+        .or_else(|| ignore_case.then(|| user_id_ignore_case(s)).flatten())
+        .ok_or_else(|| format!("Unknown user: {}", s))
+}
+
+/// Parse a group name or numeric gid for --group
+/// Names are looked up in the system group database, like for --owner
+/// With ignore_case, the name may differ from the group name in case
+/// Examples:
+/// ("root", false) -> 0
+/// ("Root", true) -> 0
+/// ("1000", false) -> 1000
+/// ("nosuchgroup", false) -> Err("Unknown group: nosuchgroup")
+pub fn parse_group(s: &str, ignore_case: bool) -> Result<u32, String> {
+    s.parse()
+        .ok()
+        .or_else(|| group_id(s))
+        .or_else(|| ignore_case.then(|| group_id_ignore_case(s)).flatten())
+        .ok_or_else(|| format!("Unknown group: {}", s))
+}
+
+/// Look up the uid of a user name with getpwnam_r
+fn user_id(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    lookup(
+        // SAFETY: the buffer and the entry outlive the call, its length is passed along
+        |pwd, buf, result| unsafe {
+            libc::getpwnam_r(name.as_ptr(), pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |pwd: &libc::passwd| pwd.pw_uid,
+    )
+}
+
+/// Look up the gid of a group name with getgrnam_r
+fn group_id(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    lookup(
+        // SAFETY: the buffer and the entry outlive the call, its length is passed along
+        |grp, buf, result| unsafe {
+            libc::getgrnam_r(name.as_ptr(), grp, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |grp: &libc::group| grp.gr_gid,
+    )
+}
+
+/// Find the uid of a user whose name differs from name only in case
+/// This walks every user with getpwent, so it's only done for -i. Users of NSS
+/// sources that don't allow listing them, like most LDAP setups, can't be found
+fn user_id_ignore_case(name: &str) -> Option<u32> {
+    let _walk = WALK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    // SAFETY: getpwent is not reentrant, WALK keeps other walks out until endpwent.
+    // Each entry is read before the next call overwrites it
+    unsafe { libc::setpwent() };
+    let id = find_id_ignore_case(name, || {
+        let pwd = unsafe { libc::getpwent().as_ref() }?;
+        Some((c_string(pwd.pw_name), pwd.pw_uid))
+    });
+    unsafe { libc::endpwent() };
+
+    id
+}
+
+/// Find the gid of a group whose name differs from name only in case, with getgrent
+/// Like user_id_ignore_case, groups that can't be listed can't be found
+fn group_id_ignore_case(name: &str) -> Option<u32> {
+    let _walk = WALK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    // SAFETY: as in user_id_ignore_case, with getgrent and endgrent
+    unsafe { libc::setgrent() };
+    let id = find_id_ignore_case(name, || {
+        let grp = unsafe { libc::getgrent().as_ref() }?;
+        Some((c_string(grp.gr_name), grp.gr_gid))
+    });
+    unsafe { libc::endgrent() };
+
+    id
+}
+
+/// Keeps walks through the user and group databases from running at once
+static WALK: Mutex<()> = Mutex::new(());
+
+/// Pick the id of the entries matching name without regard to case, the lowest
+/// one if several do
+fn find_id_ignore_case(name: &str, next: impl FnMut() -> Option<(String, u32)>) -> Option<u32> {
+    std::iter::from_fn(next)
+        .filter(|(entry, _)| entry.eq_ignore_ascii_case(name))
+        .map(|(_, id)| id)
+        .min()
+}
+
+/// Look up the name of a uid with getpwuid_r, once per uid
+fn user_name(uid: u32) -> Option<String> {
+    static USERS: NameCache = OnceLock::new();
+    cached(&USERS, uid, |uid| {
+        lookup(
+            // SAFETY: the buffer and the entry outlive the call, its length is passed along
+            |pwd, buf, result| unsafe {
+                libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result)
+            },
+            |pwd: &libc::passwd| c_string(pwd.pw_name),
+        )
+    })
+}
+
+/// Look up the name of a gid with getgrgid_r, once per gid
+fn group_name(gid: u32) -> Option<String> {
+    static GROUPS: NameCache = OnceLock::new();
+    cached(&GROUPS, gid, |gid| {
+        lookup(
+            // SAFETY: the buffer and the entry outlive the call, its length is passed along
+            |grp, buf, result| unsafe {
+                libc::getgrgid_r(gid, grp, buf.as_mut_ptr(), buf.len(), result)
+            },
+            |grp: &libc::group| c_string(grp.gr_name),
+        )
+    })
+}
+
+/// Get the name of an id from the cache, looking it up the first time
+fn cached(
+    cache: &NameCache,
+    id: u32,
+    look_up: impl FnOnce(u32) -> Option<String>,
+) -> Option<String> {
+    let mut names = cache
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    names.entry(id).or_insert_with(|| look_up(id)).clone()
+}
+
+/// Run one of the reentrant passwd or group lookups and read the entry it found
+/// The strings of the entry live in the buffer, which grows while the call
+/// reports ERANGE. No entry, or any other error, gives None
+fn lookup<T, R>(
+    call: impl Fn(&mut T, &mut [libc::c_char], &mut *mut T) -> libc::c_int,
+    read: impl Fn(&T) -> R,
+) -> Option<R> {
+    // SAFETY: passwd and group are plain C structs, all zeroes is a valid value
+    let mut entry: T = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+
+    loop {
+        let mut result = std::ptr::null_mut();
+        match call(&mut entry, &mut buf, &mut result) {
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            0 if !result.is_null() => return Some(read(&entry)),
+            _ => return None,
+        }
+    }
+}
+
+/// Copy a C string out of a passwd or group entry
+fn c_string(ptr: *const libc::c_char) -> String {
+    // SAFETY: the lookup filled in a nul terminated name, still held in its buffer
+    unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned()
+}
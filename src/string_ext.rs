@@ -1,22 +1,28 @@
+use unicode_segmentation::UnicodeSegmentation;
+
 /// Extension traits for `String` to truncate strings with an ellipsis.
 pub trait StringExt {
     fn truncate_ellipsis(&self, max_len: usize) -> String;
+    fn truncate_middle(&self, max_len: usize) -> String;
 }
 
 /// Extension traits for `&str` to truncate strings with an ellipsis.
 pub trait StrExt {
     fn truncate_ellipsis(&self, max_len: usize) -> String;
+    fn truncate_middle(&self, max_len: usize) -> String;
 }
 
 impl StringExt for String {
     /// Truncates the string to `max_len` characters, appending an ellipsis (`…`) if truncation occurs.
     /// If the string is shorter than or equal to `max_len`, it is returned unchanged
     fn truncate_ellipsis(&self, max_len: usize) -> String {
-        if self.len() > max_len {
-            format!("{}…", &self[..max_len.saturating_sub(1)])
-        } else {
-            self.clone()
-        }
+        truncate_graphemes(self, max_len)
+    }
+
+    /// Truncates the string to `max_len` characters by cutting out the middle, keeping
+    /// both ends, so long paths keep their root and their file name
+    fn truncate_middle(&self, max_len: usize) -> String {
+        truncate_graphemes_middle(self, max_len)
     }
 }
 
@@ -25,10 +31,48 @@ impl StrExt for &str {
     /// If the string is shorter than or equal to `max_len`, it is returned unchanged
     /// Returns a String instead of &str to avoid lifetime issues.
     fn truncate_ellipsis(&self, max_len: usize) -> String {
-        if self.len() > max_len {
-            format!("{}…", &self[..max_len.saturating_sub(1)])
-        } else {
-            self.to_string()
-        }
+        truncate_graphemes(self, max_len)
+    }
+
+    /// Truncates the string to `max_len` characters by cutting out the middle, keeping
+    /// both ends, so long paths keep their root and their file name
+    fn truncate_middle(&self, max_len: usize) -> String {
+        truncate_graphemes_middle(self, max_len)
+    }
+}
+
+/// Truncate by grapheme clusters, so a character and its combining marks,
+/// or a multi codepoint emoji like a flag, are never cut in half
+/// Examples:
+/// ("héllo wörld", 6) -> "héllo…"
+/// ("🇳🇱🇩🇪🇫🇷", 2) -> "🇳🇱…"
+fn truncate_graphemes(s: &str, max_len: usize) -> String {
+    if s.graphemes(true).count() <= max_len {
+        return s.to_string();
     }
+
+    let kept: String = s.graphemes(true).take(max_len.saturating_sub(1)).collect();
+    format!("{}…", kept)
+}
+
+/// Truncate by grapheme clusters in the middle, the end gets the bigger half
+/// as that is where a path keeps its file name
+/// Examples:
+/// ("/home/user/projects/fview/src/main.rs", 20) -> "/home/use…rc/main.rs"
+/// ("short", 10) -> "short"
+fn truncate_graphemes_middle(s: &str, max_len: usize) -> String {
+    let graphemes: Vec<&str> = s.graphemes(true).collect();
+    if graphemes.len() <= max_len {
+        return s.to_string();
+    }
+
+    let kept = max_len.saturating_sub(1);
+    let head = kept / 2;
+    let tail = kept - head;
+
+    format!(
+        "{}…{}",
+        graphemes[..head].concat(),
+        graphemes[graphemes.len() - tail..].concat()
+    )
 }
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+/// Fields that can be put in a --format-string, like {size}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Path,
+    /// The size in the chosen unit, e.g. "1.5 kib"
+    Size,
+    /// The exact size in bytes
+    Bytes,
+    Perms,
+    Mtime,
+    Ctime,
+    Owner,
+}
+
+/// Placeholder names a template accepts, listed when an unknown one is given
+const FIELD_NAMES: &str = "name, path, size, bytes, perms, mtime, ctime, owner";
+
+impl FromStr for Field {
+    type Err = String;
+    /// Parse a placeholder name into a Field enum
+    /// Examples:
+    /// "size" -> Field::Size
+    /// "mtime" -> Field::Mtime
+    /// "invalid" -> Err("Invalid placeholder: {invalid}, expected one of name, path, ...")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Field::Name),
+            "path" => Ok(Field::Path),
+            "size" => Ok(Field::Size),
+            "bytes" => Ok(Field::Bytes),
+            "perms" => Ok(Field::Perms),
+            "mtime" => Ok(Field::Mtime),
+            "ctime" => Ok(Field::Ctime),
+            "owner" => Ok(Field::Owner),
+            _ => Err(format!(
+                "Invalid placeholder: {{{}}}, expected one of {}",
+                s, FIELD_NAMES
+            )),
+        }
+    }
+}
+
+/// A piece of a template, text that is copied as is or a field filled in per entry
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A line of output per entry, parsed from --format-string, like `find -printf`
+/// Examples:
+/// "{name} {size}" -> "main.rs 1.5 kib"
+/// "{bytes}\t{path}" -> "1536" and "./src/main.rs" with a tab in between
+/// "{{{name}}}" -> "{main.rs}"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template, `{{` and `}}` are literal braces and `\t`, `\n` and `\\`
+    /// are a tab, a newline and a backslash
+    /// Unknown placeholders and unmatched braces are errors
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(format!(
+                                    "Invalid format string: unclosed {{ in {}",
+                                    template
+                                ));
+                            }
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(name.parse()?));
+                }
+                '}' => {
+                    return Err(format!(
+                        "Invalid format string: unmatched }} in {}",
+                        template
+                    ));
+                }
+                '\\' => match chars.next_if(|c| matches!(c, 't' | 'n' | '\\')) {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    // a backslash before anything else is kept as it is
+                    _ => text.push('\\'),
+                },
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fill in the template, every field gets the text value returns for it
+    pub fn render(&self, value: impl Fn(Field) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(field) => value(*field),
+            })
+            .collect()
+    }
+}
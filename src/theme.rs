@@ -0,0 +1,183 @@
+use crate::config_file::config_dir;
+
+use colored::{Color, Colorize};
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// An icon glyph and the color it is drawn in
+#[derive(Debug, Clone, Deserialize)]
+pub struct Icon {
+    pub glyph: String,
+    pub color: String,
+}
+
+impl Icon {
+    fn new(glyph: &str, color: &str) -> Self {
+        Icon {
+            glyph: glyph.to_string(),
+            color: color.to_string(),
+        }
+    }
+
+    /// Get the color the glyph is drawn in, white if the color name is unknown
+    pub fn color(&self) -> Color {
+        Color::from_str(&self.color).unwrap_or(Color::White)
+    }
+
+    /// Render the glyph in its color
+    /// The color goes through colored, so it is dropped along with every other
+    /// color when colors are off, while the glyph stays
+    pub fn render(&self) -> String {
+        self.glyph.color(self.color()).to_string()
+    }
+}
+
+/// Color names that match a glob pattern, e.g. to mute generated or test files
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColorRule {
+    pub glob: String,
+    pub color: String,
+    /// Compiled from glob when the theme is loaded
+    #[serde(skip)]
+    matcher: Option<GlobMatcher>,
+}
+
+/// Icons and colors used for file names
+/// Loaded from `--theme PATH` or `theme.toml` in the config dir, any missing
+/// field falls back to the built-in default
+///
+/// Example:
+/// [dir]
+/// glyph = "\uf4d4"
+/// color = "blue"
+///
+/// [extensions.rs]
+/// glyph = "\ue7a8"
+/// color = "red"
+///
+/// [[rules]]
+/// glob = "*.test.ts"
+/// color = "bright black"
+///
+/// Rules color the file name and are tried in order, the first match wins.
+/// Icons keep their type or extension color, names without a matching rule stay uncolored
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub symlink: Icon,
+    pub dir: Icon,
+    pub empty_dir: Icon,
+    pub file: Icon,
+    pub socket: Icon,
+    pub fifo: Icon,
+    pub block_device: Icon,
+    pub char_device: Icon,
+    /// Icons for files by extension, these win over the generic file icon
+    pub extensions: HashMap<String, Icon>,
+    /// Name colors by glob, in order of precedence
+    pub rules: Vec<ColorRule>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            symlink: Icon::new("\u{f481}", "cyan"),
+            dir: Icon::new("\u{f4d4}", "blue"),
+            empty_dir: Icon::new("\u{f4d3}", "blue"),
+            file: Icon::new("\u{f15b}", "green"),
+            socket: Icon::new("\u{f1e6}", "magenta"),
+            fifo: Icon::new("\u{f07e}", "yellow"),
+            block_device: Icon::new("\u{f0a0}", "bright yellow"),
+            char_device: Icon::new("\u{f11c}", "bright yellow"),
+            extensions: HashMap::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Theme {
+    /// Get the icon for a file extension, matched case insensitively
+    pub fn extension_icon(&self, ext: &str) -> Option<&Icon> {
+        self.extensions
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(ext))
+            .map(|(_, icon)| icon)
+    }
+
+    /// Get the color of the first rule whose glob matches the file name
+    pub fn rule_color(&self, name: &str) -> Option<Color> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matcher.as_ref().is_some_and(|m| m.is_match(name)))
+            .and_then(|rule| Color::from_str(&rule.color).ok())
+    }
+
+    /// Check that every color in the theme is one we know how to draw
+    fn validate(&self) -> Result<(), String> {
+        let colors = [
+            &self.symlink,
+            &self.dir,
+            &self.empty_dir,
+            &self.file,
+            &self.socket,
+            &self.fifo,
+            &self.block_device,
+            &self.char_device,
+        ]
+        .into_iter()
+        .chain(self.extensions.values())
+        .map(|icon| &icon.color)
+        .chain(self.rules.iter().map(|rule| &rule.color));
+
+        for color in colors {
+            if Color::from_str(color).is_err() {
+                return Err(format!("Invalid theme color: {}", color));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compile the glob of every rule
+    fn compile_rules(&mut self) -> Result<(), String> {
+        for rule in &mut self.rules {
+            let glob = Glob::new(&rule.glob)
+                .map_err(|e| format!("Invalid theme glob {}: {}", rule.glob, e))?;
+            rule.matcher = Some(glob.compile_matcher());
+        }
+
+        Ok(())
+    }
+}
+
+/// Load the theme to use
+/// If a path is given it must exist, otherwise `theme.toml` in the config dir
+/// is used when present, falling back to the built-in default
+pub fn load_theme(path: Option<&str>) -> Result<Theme, String> {
+    let path = match path {
+        Some(path) => Path::new(path).to_path_buf(),
+        None => match config_dir().map(|dir| dir.join("theme.toml")) {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Theme::default()),
+        },
+    };
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read theme {}: {}", path.display(), e))?;
+
+    let mut theme: Theme = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    }
+    .map_err(|e| format!("Failed to parse theme {}: {}", path.display(), e))?;
+
+    theme.validate()?;
+    theme.compile_rules()?;
+
+    Ok(theme)
+}
@@ -0,0 +1,118 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A file in the trash, read from its .trashinfo file
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    /// Where the file lives inside the trash
+    pub path: PathBuf,
+    /// Where the file was before it got deleted
+    pub original_path: PathBuf,
+    /// When the file was deleted
+    pub deleted: Option<SystemTime>,
+}
+
+/// Get the trash directory of the current user
+/// Lives at `$XDG_DATA_HOME/Trash` or `~/.local/share/Trash`
+pub fn trash_dir() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+        })?;
+
+    Some(data_dir.join("Trash"))
+}
+
+/// List the files in the trash, following the freedesktop trash spec
+/// Every file in `files/` has a matching `info/<name>.trashinfo` with its
+/// original path and deletion date. Files without readable info are left out
+/// The result is ordered by deletion date, oldest first
+pub fn list_trash() -> Result<Vec<TrashItem>, String> {
+    let trash = trash_dir().ok_or("Could not determine the trash directory")?;
+    let info_dir = trash.join("info");
+
+    let read_dir = match fs::read_dir(&info_dir) {
+        Ok(read_dir) => read_dir,
+        // no trash yet means nothing was deleted
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", info_dir.display(), e)),
+    };
+
+    let mut items: Vec<TrashItem> = read_dir
+        .flatten()
+        .filter_map(|info| {
+            let info_path = info.path();
+            let name = info_path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".trashinfo")?
+                .to_string();
+            let contents = fs::read_to_string(&info_path).ok()?;
+
+            parse_trash_info(&contents).map(|(original_path, deleted)| TrashItem {
+                path: trash.join("files").join(name),
+                original_path,
+                deleted,
+            })
+        })
+        .collect();
+
+    items.sort_by_key(|item| item.deleted);
+    Ok(items)
+}
+
+/// Parse the original path and deletion date out of a .trashinfo file
+/// Example:
+/// [Trash Info]
+/// Path=/home/user/my%20notes.txt
+/// DeletionDate=2024-05-01T12:34:56
+fn parse_trash_info(contents: &str) -> Option<(PathBuf, Option<SystemTime>)> {
+    let mut path = None;
+    let mut deleted = None;
+
+    for line in contents.lines() {
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(PathBuf::from(percent_decode(value)));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deleted = NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .and_then(|date| Local.from_local_datetime(&date).single())
+                .map(SystemTime::from);
+        }
+    }
+
+    Some((path?, deleted))
+}
+
+/// Decode %XX escapes in a trash info path
+/// Examples:
+/// "my%20notes.txt" -> "my notes.txt"
+/// "100%" -> "100%"
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
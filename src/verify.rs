@@ -0,0 +1,129 @@
+use crate::hash;
+use crate::log;
+
+use colored::*;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A file listed in a sums file with the hash it should have
+#[derive(Debug, Clone, PartialEq)]
+pub struct SumLine {
+    /// Lowercase hex SHA-256
+    pub hash: String,
+    pub path: PathBuf,
+}
+
+/// What checking a listed file against its hash found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// The contents match the listed hash
+    Ok,
+    /// The contents differ from the listed hash
+    Failed,
+    /// The file doesn't exist or can't be read
+    Missing,
+}
+
+/// Counts of the outcomes of a verification
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VerifyReport {
+    pub ok: usize,
+    pub failed: usize,
+    pub missing: usize,
+}
+
+impl VerifyReport {
+    /// Check if every listed file was found with the right contents
+    pub fn is_ok(&self) -> bool {
+        self.failed == 0 && self.missing == 0
+    }
+}
+
+/// Parse one line of a `sha256sum` style file, "hash  filename"
+/// A `*` in front of the file name marks binary mode and is dropped
+/// Examples:
+/// "e3b0...b855  empty.txt" -> SumLine { hash: "e3b0...b855", path: "empty.txt" }
+/// "E3B0...B855 *empty.txt" -> SumLine { hash: "e3b0...b855", path: "empty.txt" }
+/// "not a sum line" -> None
+pub fn parse_sum_line(line: &str) -> Option<SumLine> {
+    let (hash, rest) = line.split_once(' ')?;
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+    if path.is_empty() {
+        return None;
+    }
+
+    Some(SumLine {
+        hash: hash.to_ascii_lowercase(),
+        path: PathBuf::from(path),
+    })
+}
+
+/// Read a sums file, skipping blank lines and `#` comments
+/// Lines that aren't in the "hash  filename" format are reported and skipped
+pub fn read_sums_file(path: &str) -> Result<Vec<SumLine>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Error reading sums file {}: {}", path, e))?;
+
+    let mut sums = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_sum_line(line) {
+            Some(sum) => sums.push(sum),
+            None => log::warn(format!(
+                "{}:{}: improperly formatted checksum line",
+                path,
+                number + 1
+            )),
+        }
+    }
+
+    Ok(sums)
+}
+
+/// Hash every listed file and write one "path: STATUS" line for each, like `sha256sum -c`
+/// Relative paths are looked up in `dir`, the files are hashed on `jobs` threads
+pub fn verify(
+    sums: &[SumLine],
+    dir: &Path,
+    jobs: usize,
+    out: &mut impl Write,
+) -> io::Result<VerifyReport> {
+    let paths: Vec<PathBuf> = sums.iter().map(|sum| dir.join(&sum.path)).collect();
+    let hashes = hash::hash_files(&paths, jobs);
+    let mut report = VerifyReport::default();
+
+    for (sum, path) in sums.iter().zip(&paths) {
+        let status = match hashes.get(path) {
+            Some(hash) if *hash == sum.hash => Status::Ok,
+            Some(_) => Status::Failed,
+            None => Status::Missing,
+        };
+
+        let text = match status {
+            Status::Ok => {
+                report.ok += 1;
+                "OK".green()
+            }
+            Status::Failed => {
+                report.failed += 1;
+                "FAILED".red()
+            }
+            Status::Missing => {
+                report.missing += 1;
+                "MISSING".yellow()
+            }
+        };
+        writeln!(out, "{}: {}", sum.path.display(), text)?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use common::Fixture;
+
+use std::process::{Command, Output, Stdio};
+
+/// Run the fview binary with the given arguments
+fn fview(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fview"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn stable_output_is_byte_identical() {
+    let fixture = Fixture::new();
+    // the same size everywhere, so only the tie breaker decides the order
+    for name in ["b", "a", "d", "c", "sub/z", "sub/y", "sub/x"] {
+        fixture.file(name, 10);
+    }
+    let dir = fixture.path().to_str().unwrap();
+    let args = ["--stable", "--sort", "size", "-d", "3", dir];
+
+    let first = fview(&args);
+    let second = fview(&args);
+    assert!(first.status.success());
+    assert!(!first.stdout.is_empty());
+    assert_eq!(first.stdout, second.stdout);
+}
+
+#[test]
+fn closing_the_pipe_early_exits_cleanly() {
+    let fixture = Fixture::new();
+    // more than a pipe buffer holds, so the writes can't all finish before the close
+    for i in 0..2000 {
+        fixture.file(&format!("file-with-a-long-name-{:04}.txt", i), 1);
+    }
+    let dir = fixture.path().to_str().unwrap();
+
+    for args in [vec![dir], vec!["--json-stream", dir]] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_fview"))
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // like `fview | head -0`, the reader is gone before anything is read
+        drop(child.stdout.take());
+
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(0), "{:?}: {}", args, stderr);
+        assert!(!stderr.contains("panicked"), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn exec_runs_on_the_listed_entries_in_order() {
+    let fixture = Fixture::new();
+    let b = fixture.file("b.txt", 1);
+    let a = fixture.file("a.txt", 1);
+    fixture.file("c.bin", 1);
+    let dir = fixture.path().to_str().unwrap();
+
+    let output = fview(&[
+        "--glob",
+        "*.txt",
+        "--sort",
+        "name",
+        "--hash",
+        "--exec",
+        "echo RUN {}",
+        dir,
+    ]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let runs: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("RUN "))
+        .collect();
+    assert_eq!(runs, [a.to_str().unwrap(), b.to_str().unwrap()]);
+    assert_eq!(
+        stdout.lines().filter(|line| line.contains(".txt")).count(),
+        4
+    );
+}
+
+#[test]
+fn trash_is_sorted_like_any_listing() {
+    let data = Fixture::new();
+    for (file, original, size, deleted) in [
+        ("x", "b.txt", 300, "2024-01-01T10:00:00"),
+        ("y", "c.txt", 100, "2024-01-03T10:00:00"),
+        ("z", "a.txt", 200, "2024-01-02T10:00:00"),
+    ] {
+        data.file(&format!("Trash/files/{}", file), size);
+        let info = format!(
+            "[Trash Info]\nPath=/home/user/{}\nDeletionDate={}\n",
+            original, deleted
+        );
+        std::fs::write(
+            data.dir("Trash/info").join(format!("{}.trashinfo", file)),
+            info,
+        )
+        .unwrap();
+    }
+
+    let trash = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_fview"))
+            .args(["--trash", "--no-header", "--no-icons"])
+            .args(args)
+            .env("XDG_DATA_HOME", data.path())
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let names = |names: [&str; 3]| names.map(|name| format!("/home/user/{}", name));
+
+    // by when they were deleted by default, the date the listing shows
+    assert_eq!(trash(&[]), names(["b.txt", "a.txt", "c.txt"]));
+    assert_eq!(trash(&["-r"]), names(["c.txt", "a.txt", "b.txt"]));
+    assert_eq!(
+        trash(&["--sort", "name"]),
+        names(["a.txt", "b.txt", "c.txt"])
+    );
+    assert_eq!(
+        trash(&["--sort", "size"]),
+        names(["c.txt", "a.txt", "b.txt"])
+    );
+    assert_eq!(
+        trash(&["--sort", "name", "-r"]),
+        names(["c.txt", "b.txt", "a.txt"])
+    );
+}
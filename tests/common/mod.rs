@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+
+use fview::config::{Config, view_files};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A directory tree for a test to list, removed again when it goes out of scope
+/// Every fixture gets its own directory, so tests can run in parallel
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "fview-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&root).unwrap();
+
+        Fixture { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Create a file of `size` bytes, and the directories it is in
+    pub fn file(&self, name: &str, size: usize) -> PathBuf {
+        let path = self.root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        path
+    }
+
+    pub fn dir(&self, name: &str) -> PathBuf {
+        let path = self.root.join(name);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    pub fn symlink(&self, name: &str, target: impl AsRef<Path>) -> PathBuf {
+        let path = self.root.join(name);
+        std::os::unix::fs::symlink(target, &path).unwrap();
+        path
+    }
+
+    /// A config listing the fixture, with nothing but the file names in the rows
+    pub fn config(&self) -> Config {
+        Config {
+            dir: self.root.to_string_lossy().into_owned(),
+            no_icons: true,
+            header: false,
+            ..Default::default()
+        }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Render a listing to a string, without colors
+pub fn render(config: Config) -> String {
+    colored::control::set_override(false);
+
+    let mut out = Vec::new();
+    view_files(Some(config), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Names of the entries a config collects, in listing order
+pub fn names(config: &Config) -> Vec<String> {
+    fview::config::collect_entries(config)
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect()
+}
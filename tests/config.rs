@@ -0,0 +1,99 @@
+#![cfg(feature = "cli")]
+
+use clap::Parser;
+use fview::cli::Args;
+use fview::config::{ColorMode, Config, SortKey, Unit, merge_config};
+use fview::config_file::Profile;
+
+/// Merge a command line with FVIEW_* variables and a profile from the config file
+fn merge(args: &[&str], env: &[(&str, &str)], file: &str) -> Config {
+    let args = Args::try_parse_from(["fview"].iter().chain(args)).unwrap();
+    let env = Profile::from_env(
+        env.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string())),
+    )
+    .unwrap();
+    let file: Profile = toml::from_str(file).unwrap();
+
+    merge_config(args, &env, Some(&file)).unwrap()
+}
+
+#[test]
+fn file_sets_options_nothing_else_sets() {
+    let config = merge(
+        &[],
+        &[],
+        "max_depth = 3\nshow_hidden = true\nsort = \"size\"",
+    );
+
+    assert_eq!(config.max_depth, Some(3));
+    assert!(config.show_hidden);
+    assert_eq!(config.sort, Some(SortKey::Size));
+}
+
+#[test]
+fn env_overrides_file() {
+    let config = merge(
+        &[],
+        &[
+            ("FVIEW_SHOW_HIDDEN", "0"),
+            ("FVIEW_MAX_DEPTH", "5"),
+            ("FVIEW_SORT", "name"),
+        ],
+        "max_depth = 3\nshow_hidden = true\nsort = \"size\"",
+    );
+
+    assert_eq!(config.max_depth, Some(5));
+    assert!(!config.show_hidden);
+    assert_eq!(config.sort, Some(SortKey::Name));
+}
+
+#[test]
+fn cli_overrides_env_and_file() {
+    let config = merge(
+        &["-H", "-d", "2", "--sort", "modified"],
+        &[
+            ("FVIEW_SHOW_HIDDEN", "0"),
+            ("FVIEW_MAX_DEPTH", "5"),
+            ("FVIEW_SORT", "name"),
+        ],
+        "max_depth = 3\nshow_hidden = false\nsort = \"size\"",
+    );
+
+    assert_eq!(config.max_depth, Some(2));
+    assert!(config.show_hidden);
+    assert_eq!(config.sort, Some(SortKey::Modified));
+}
+
+#[test]
+fn invalid_file_value_is_an_error() {
+    assert!(toml::from_str::<Profile>("sort = \"sideways\"").is_err());
+}
+
+#[test]
+fn explicit_auto_color_overrides_profile() {
+    let file = "color = \"never\"";
+
+    assert_eq!(merge(&[], &[], file).color, ColorMode::Never);
+    assert_eq!(
+        merge(&["--color", "auto"], &[], file).color,
+        ColorMode::Auto
+    );
+    assert_eq!(
+        merge(&[], &[("FVIEW_COLOR", "auto")], file).color,
+        ColorMode::Auto
+    );
+}
+
+#[test]
+fn unit_defaults_to_bytes() {
+    assert!(merge(&[], &[], "").unit.is_none());
+    assert!(matches!(
+        merge(&["--bytes"], &[], "").unit,
+        Some(Unit::Bytes)
+    ));
+    assert!(matches!(
+        merge(&["--pretty"], &[], "").unit,
+        Some(Unit::Auto)
+    ));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{Fixture, render};
+use fview::config::{Config, collect_entries, normalize_start_dir};
+use fview::diff::{Change, diff_listings};
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Write a file with a fixed modification time, so unchanged files compare equal
+fn file(fixture: &Fixture, name: &str, size: usize) {
+    let path = fixture.file(name, size);
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+/// Two trees with one of each kind of change at the top and in a subdirectory
+fn fixtures() -> (Fixture, Fixture) {
+    let old = Fixture::new();
+    file(&old, "same.txt", 10);
+    file(&old, "changed.txt", 10);
+    file(&old, "removed.txt", 10);
+    file(&old, "sub/gone.txt", 10);
+
+    let new = Fixture::new();
+    file(&new, "same.txt", 10);
+    file(&new, "changed.txt", 20);
+    file(&new, "added.txt", 10);
+    file(&new, "sub/new.txt", 10);
+
+    (old, new)
+}
+
+fn config(dir: &Path) -> Config {
+    Config {
+        dir: dir.to_string_lossy().into_owned(),
+        max_depth: Some(2),
+        no_icons: true,
+        header: false,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn entries_are_classified_as_added_removed_or_changed() {
+    let (old, new) = fixtures();
+
+    let diff = diff_listings(
+        collect_entries(&config(old.path())),
+        &normalize_start_dir(&old.path().to_string_lossy()),
+        collect_entries(&config(new.path())),
+        &normalize_start_dir(&new.path().to_string_lossy()),
+    );
+    let changes: Vec<(Change, PathBuf)> = diff
+        .into_iter()
+        .map(|line| (line.change, line.relative_path))
+        .collect();
+
+    assert_eq!(
+        changes,
+        [
+            (Change::Added, PathBuf::from("added.txt")),
+            (Change::Changed, PathBuf::from("changed.txt")),
+            (Change::Removed, PathBuf::from("removed.txt")),
+            (Change::Removed, PathBuf::from("sub/gone.txt")),
+            (Change::Added, PathBuf::from("sub/new.txt")),
+        ]
+    );
+}
+
+#[test]
+fn diff_stat_counts_each_kind_of_change() {
+    let (old, new) = fixtures();
+
+    let stat = render(Config {
+        diff: Some(old.path().to_string_lossy().into_owned()),
+        diff_stat: true,
+        ..config(new.path())
+    });
+    assert_eq!(stat, "+2 -2 ~1\n");
+
+    let lines = render(Config {
+        diff: Some(old.path().to_string_lossy().into_owned()),
+        ..config(new.path())
+    });
+    let prefixes: Vec<&str> = lines.lines().map(|line| &line[..1]).collect();
+    assert_eq!(prefixes, ["+", "~", "-", "-", "+"]);
+}
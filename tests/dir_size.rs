@@ -0,0 +1,108 @@
+mod common;
+
+use common::{Fixture, render};
+use fview::config::{Config, collect_entries};
+use fview::dir_size::dir_size;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+#[test]
+fn symlinks_count_as_themselves() {
+    let outside = Fixture::new();
+    outside.file("big.bin", 10_000);
+
+    let fixture = Fixture::new();
+    fixture.file("a.txt", 100);
+    let link = fixture.symlink("outside", outside.path());
+    let link_len = fs::symlink_metadata(&link).unwrap().len();
+
+    let size = dir_size(fixture.path(), false, None, None);
+    assert_eq!(size.bytes, 100 + link_len);
+    assert!(!size.is_partial());
+
+    let total = render(Config {
+        total_only: true,
+        ..fixture.config()
+    });
+    assert_eq!(total.trim(), format!("{} b", 100 + link_len));
+}
+
+#[test]
+fn symlink_to_an_ancestor_is_not_walked() {
+    let fixture = Fixture::new();
+    fixture.file("sub/a.txt", 100);
+    let link = fixture.symlink("sub/up", fixture.path());
+    let link_len = fs::symlink_metadata(&link).unwrap().len();
+
+    let size = dir_size(fixture.path(), false, None, None);
+    assert_eq!(size.bytes, 100 + link_len);
+    assert!(!size.is_partial());
+}
+
+#[test]
+fn linked_directory_entry_is_not_summed() {
+    let outside = Fixture::new();
+    outside.file("big.bin", 10_000);
+
+    let fixture = Fixture::new();
+    let link = fixture.symlink("outside", outside.path());
+
+    let config = Config {
+        dir_size: true,
+        ..fixture.config()
+    };
+    let entries = collect_entries(&config);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].size,
+        Some(fs::symlink_metadata(&link).unwrap().len())
+    );
+}
+
+#[test]
+fn unreadable_directory_makes_the_total_partial() {
+    let fixture = Fixture::new();
+    fixture.file("a.txt", 100);
+    let locked = fixture.dir("locked");
+    fixture.file("locked/b.txt", 100);
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+    // root can read it anyway, then there is nothing to test
+    let readable = fs::read_dir(&locked).is_ok();
+    let size = dir_size(fixture.path(), false, None, None);
+    let total = render(Config {
+        total_only: true,
+        ..fixture.config()
+    });
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    if readable {
+        return;
+    }
+
+    assert_eq!(size.bytes, 100);
+    assert_eq!(size.unreadable, 1);
+    assert!(size.is_partial());
+    assert_eq!(total.trim(), "100 b+");
+}
+
+#[test]
+fn entries_that_cant_be_read_make_the_total_partial() {
+    let fixture = Fixture::new();
+    fixture.file("a.txt", 100);
+    let listable = fixture.dir("listable");
+    fixture.file("listable/b.txt", 100);
+    // the names can be listed, but without x on the directory nothing in it can be read
+    fs::set_permissions(&listable, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let readable = fs::symlink_metadata(listable.join("b.txt")).is_ok();
+    let size = dir_size(fixture.path(), false, None, None);
+    fs::set_permissions(&listable, fs::Permissions::from_mode(0o755)).unwrap();
+    if readable {
+        return;
+    }
+
+    assert_eq!(size.bytes, 100);
+    assert_eq!(size.unreadable, 1);
+    assert!(size.is_partial());
+}
@@ -0,0 +1,25 @@
+use fview::exec::batches;
+
+use std::path::PathBuf;
+
+#[test]
+fn batches_stay_under_the_budget() {
+    let paths: Vec<PathBuf> = ["a", "bb", "c", "dddd", "e"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    // every path takes its bytes, a nul and a pointer
+    let size = |len: usize| len + 1 + size_of::<usize>();
+
+    let budget = size(1) + size(2);
+    let split = batches(&paths, budget);
+    assert_eq!(split.concat(), paths);
+    assert_eq!(
+        split.iter().map(|batch| batch.len()).collect::<Vec<_>>(),
+        [2, 1, 1, 1]
+    );
+
+    // a path too big for any batch still gets one of its own
+    assert_eq!(batches(&paths, 1).len(), paths.len());
+    assert_eq!(batches(&paths, usize::MAX), [paths.as_slice()]);
+}
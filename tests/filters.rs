@@ -0,0 +1,126 @@
+mod common;
+
+use common::Fixture;
+use fview::config::{Config, SortKey, collect_entries, exec_paths};
+use globset::Glob;
+
+#[test]
+fn context_rows_are_not_passed_to_exec() {
+    let fixture = Fixture::new();
+    fixture.file("a.bin", 10);
+    let matched = fixture.file("b.txt", 10);
+    fixture.dir("c");
+
+    let config = Config {
+        globs: vec![Glob::new("*.txt").unwrap().compile_matcher()],
+        context: Some(1),
+        sort: Some(SortKey::Name),
+        ..fixture.config()
+    };
+
+    let entries = collect_entries(&config);
+    assert_eq!(entries.iter().filter(|entry| entry.context).count(), 2);
+    assert_eq!(exec_paths(&entries), vec![matched]);
+}
+
+/// -i needs the command line, the globs and the regex are built from it
+#[cfg(feature = "cli")]
+mod ignore_case {
+    use super::*;
+    use clap::Parser;
+    use fview::cli::Args;
+    use fview::config::merge_config;
+    use fview::config_file::Profile;
+
+    /// Names listed with the given command line, sorted so the order is fixed
+    fn listed(fixture: &Fixture, args: &[&str]) -> Result<Vec<String>, String> {
+        let dir = fixture.path().to_str().unwrap();
+        let args = Args::try_parse_from(["fview", dir].iter().chain(args)).unwrap();
+        let config = merge_config(args, &Profile::default(), None)?;
+
+        let mut names = common::names(&config);
+        names.sort();
+        Ok(names)
+    }
+
+    fn mixed_case_fixture() -> Fixture {
+        let fixture = Fixture::new();
+        fixture.file("main.rs", 1);
+        fixture.file("readme", 1);
+        fixture.file("Notes.TXT", 1);
+        fixture
+    }
+
+    #[test]
+    fn ignore_case_applies_to_globs() {
+        let fixture = mixed_case_fixture();
+
+        assert!(listed(&fixture, &["--glob", "*.txt"]).unwrap().is_empty());
+        assert_eq!(
+            listed(&fixture, &["--glob", "*.txt", "-i"]).unwrap(),
+            ["Notes.TXT"]
+        );
+        assert_eq!(
+            listed(
+                &fixture,
+                &["--exclude", "*.TXT", "--exclude", "README", "-i"]
+            )
+            .unwrap(),
+            ["main.rs"]
+        );
+    }
+
+    #[test]
+    fn ignore_case_applies_to_the_regex() {
+        let fixture = mixed_case_fixture();
+
+        assert!(listed(&fixture, &["--regex", "README"]).unwrap().is_empty());
+        assert_eq!(
+            listed(&fixture, &["--regex", "README", "-i"]).unwrap(),
+            ["readme"]
+        );
+    }
+
+    #[test]
+    fn ignore_case_applies_to_extensions() {
+        let fixture = mixed_case_fixture();
+
+        assert!(listed(&fixture, &["--ext", "RS"]).unwrap().is_empty());
+        assert_eq!(
+            listed(&fixture, &["--ext", "RS", "-i"]).unwrap(),
+            ["main.rs"]
+        );
+        assert_eq!(
+            listed(&fixture, &["--ext", "txt", "-i"]).unwrap(),
+            ["Notes.TXT"]
+        );
+    }
+
+    #[test]
+    fn ignore_case_applies_to_the_fuzzy_query() {
+        let fixture = mixed_case_fixture();
+
+        // a query with capitals is matched case sensitively, unless -i
+        assert!(listed(&fixture, &["--fuzzy", "NOT"]).unwrap().is_empty());
+        assert_eq!(
+            listed(&fixture, &["--fuzzy", "NOT", "-i"]).unwrap(),
+            ["Notes.TXT"]
+        );
+    }
+
+    #[test]
+    fn ignore_case_applies_to_owner_names() {
+        let fixture = mixed_case_fixture();
+
+        assert!(listed(&fixture, &["--owner", "ROOT"]).is_err());
+        assert!(listed(&fixture, &["--group", "ROOT"]).is_err());
+        assert_eq!(
+            listed(&fixture, &["--owner", "ROOT", "-i"]).unwrap(),
+            listed(&fixture, &["--owner", "root"]).unwrap()
+        );
+        assert_eq!(
+            listed(&fixture, &["--group", "ROOT", "-i"]).unwrap(),
+            listed(&fixture, &["--group", "root"]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{Fixture, render};
+use fview::config::{Config, SortKey, TimeField};
+use fview::string_ext::StrExt;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A combining acute accent, it belongs to the character before it
+const ACCENT: &str = "\u{301}";
+
+#[test]
+fn truncation_keeps_combining_accents_with_their_letter() {
+    let name = format!("cafe{}-menu.txt", ACCENT);
+
+    assert_eq!(
+        name.as_str().truncate_ellipsis(5),
+        format!("cafe{}…", ACCENT)
+    );
+    let accented = format!("e{}", ACCENT);
+    assert_eq!(
+        accented.repeat(10).as_str().truncate_middle(5),
+        format!("{0}{0}…{0}{0}", accented)
+    );
+}
+
+#[test]
+fn truncation_keeps_flags_whole() {
+    let flags = "🇳🇱🇩🇪🇫🇷🇮🇹";
+
+    assert_eq!(flags.truncate_ellipsis(3), "🇳🇱🇩🇪…");
+    assert_eq!(flags.truncate_ellipsis(4), flags);
+}
+
+#[test]
+fn names_with_combining_accents_and_flags_line_up() {
+    let fixture = Fixture::new();
+    fixture.file("plain.txt", 1);
+    fixture.file(&format!("cafe{}.txt", ACCENT), 1);
+    fixture.file("🇳🇱🇩🇪.txt", 1);
+    // too long for the column, so it is cut short
+    fixture.file(&format!("{}.txt", format!("e{}", ACCENT).repeat(20)), 1);
+
+    let width = 16;
+    let listing = render(Config {
+        name_width: Some(width),
+        sort: Some(SortKey::Name),
+        time: Some(TimeField::Modified),
+        ..fixture.config()
+    });
+    let rows: Vec<Vec<&str>> = listing
+        .lines()
+        .map(|row| row.graphemes(true).collect())
+        .collect();
+
+    assert_eq!(rows.len(), 4);
+    for row in &rows {
+        // every row is as long, and the name column ends at the same place
+        assert_eq!(row.len(), rows[0].len());
+        assert_eq!(row[width - 1], " ");
+    }
+
+    let names: Vec<String> = rows
+        .iter()
+        .map(|row| row[..width].concat().trim_end().to_string())
+        .collect();
+    assert!(names.contains(&format!("cafe{}.txt", ACCENT)));
+    assert!(names.contains(&"🇳🇱🇩🇪.txt".to_string()));
+    assert!(names.contains(&format!("{}…", format!("e{}", ACCENT).repeat(14))));
+}
+
+#[test]
+fn canonical_paths_keep_the_file_name_when_cut() {
+    let fixture = Fixture::new();
+    let deep = "a-rather-long-directory/with-another-long-one/and-one-more-level";
+    fixture.file(&format!("{}/alpha.txt", deep), 1);
+    fixture.file(&format!("{}/gamma.txt", deep), 1);
+
+    let width = 24;
+    let listing = render(Config {
+        dir: fixture.path().join(deep).to_string_lossy().into_owned(),
+        canonicalize: true,
+        name_width: Some(width),
+        sort: Some(SortKey::Name),
+        ..fixture.config()
+    });
+    let names: Vec<String> = listing
+        .lines()
+        .map(|row| row.graphemes(true).take(width).collect::<String>())
+        .map(|name| name.trim().to_string())
+        .collect();
+
+    assert_eq!(names.len(), 2);
+    for (name, file) in names.iter().zip(["alpha.txt", "gamma.txt"]) {
+        // the shared parent directories lose their middle, the file name stays
+        let (_, tail) = name.split_once('…').unwrap();
+        assert!(tail.ends_with(&format!("/{}", file)), "{}", name);
+    }
+    assert_ne!(names[0], names[1]);
+}
@@ -0,0 +1,31 @@
+use fview::owner::{file_owner, parse_group, parse_user};
+
+use std::path::Path;
+
+#[test]
+fn owner_names_come_from_the_user_database() {
+    let root = Path::new("/");
+
+    assert_eq!(file_owner(root, false).as_deref(), Some("root root"));
+    assert_eq!(file_owner(root, true).as_deref(), Some("0 0"));
+}
+
+#[test]
+fn owner_filters_take_names_or_ids() {
+    assert_eq!(parse_user("root", false), Ok(0));
+    assert_eq!(parse_user("1000", false), Ok(1000));
+    assert_eq!(parse_group("root", false), Ok(0));
+
+    assert!(parse_user("ROOT", false).is_err());
+    assert_eq!(parse_user("ROOT", true), Ok(0));
+    assert_eq!(parse_group("Root", true), Ok(0));
+
+    assert_eq!(
+        parse_user("nosuchuser", true),
+        Err("Unknown user: nosuchuser".to_string())
+    );
+    assert_eq!(
+        parse_group("nosuchgroup", false),
+        Err("Unknown group: nosuchgroup".to_string())
+    );
+}
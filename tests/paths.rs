@@ -0,0 +1,77 @@
+mod common;
+
+use common::{Fixture, names};
+use fview::config::{Config, SortKey, absolute_dir, collect_entries, normalize_start_dir};
+
+use std::path::{Path, PathBuf};
+
+/// A config listing `dir` by name, so the order doesn't depend on timestamps
+fn listing(fixture: &Fixture, dir: String) -> Config {
+    Config {
+        dir,
+        sort: Some(SortKey::Name),
+        ..fixture.config()
+    }
+}
+
+#[test]
+fn trailing_slashes_are_dropped() {
+    assert_eq!(normalize_start_dir("src///"), PathBuf::from("src"));
+    assert_eq!(
+        normalize_start_dir("./src/./config/"),
+        PathBuf::from("./src/config")
+    );
+
+    let fixture = Fixture::new();
+    fixture.file("sub/a.txt", 1);
+    fixture.file("sub/b.txt", 1);
+    let sub = fixture.path().join("sub").to_string_lossy().into_owned();
+
+    let expected = vec!["a.txt", "b.txt"];
+    assert_eq!(names(&listing(&fixture, sub.clone())), expected);
+    assert_eq!(names(&listing(&fixture, format!("{}/", sub))), expected);
+    assert_eq!(names(&listing(&fixture, format!("{}///", sub))), expected);
+}
+
+#[test]
+fn symlinked_start_dir_lists_the_target() {
+    let fixture = Fixture::new();
+    let target = fixture.dir("target");
+    fixture.file("target/a.txt", 1);
+    fixture.file("target/b.txt", 1);
+    let link = fixture.symlink("link", &target);
+    let link = link.to_string_lossy().into_owned();
+
+    assert_eq!(normalize_start_dir(&link), target.canonicalize().unwrap());
+
+    let expected = vec!["a.txt", "b.txt"];
+    assert_eq!(names(&listing(&fixture, link.clone())), expected);
+    assert_eq!(names(&listing(&fixture, format!("{}/", link))), expected);
+}
+
+#[test]
+fn relative_dirs_are_made_absolute() {
+    let cwd = std::env::current_dir().unwrap();
+    let absolute = |dir| PathBuf::from(absolute_dir(dir));
+
+    assert_eq!(absolute("."), cwd);
+    assert_eq!(absolute("./"), cwd);
+    assert_eq!(absolute("src"), cwd.join("src"));
+    assert_eq!(absolute("/tmp"), Path::new("/tmp"));
+}
+
+#[test]
+fn names_stay_relative_for_an_absolute_dir() {
+    let cwd = std::env::current_dir().unwrap();
+    let config = Config {
+        dir: absolute_dir("src"),
+        ..Config::default()
+    };
+
+    let entries = collect_entries(&config);
+    let main = entries
+        .iter()
+        .find(|entry| entry.name == "main.rs")
+        .unwrap();
+    assert_eq!(main.path, cwd.join("src").join("main.rs"));
+}
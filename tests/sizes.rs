@@ -0,0 +1,42 @@
+mod common;
+
+use common::{Fixture, render};
+use fview::config::{Config, Rounding, Unit};
+
+#[test]
+fn sizes_are_in_bytes_by_default() {
+    assert!(Config::default().unit.is_none());
+    assert!(matches!(Unit::default(), Unit::Bytes));
+
+    let fixture = Fixture::new();
+    fixture.file("a.bin", 1500);
+
+    let listing = render(fixture.config());
+    assert!(listing.contains("1500 b"), "{}", listing);
+}
+
+#[test]
+fn totals_add_up_bytes_before_scaling() {
+    let fixture = Fixture::new();
+    for size in [500, 600, 700] {
+        fixture.file(&format!("{}.bin", size), size);
+    }
+    let total = |unit, size_rounding, dual_units| {
+        render(Config {
+            total_only: true,
+            unit,
+            size_rounding,
+            dual_units,
+            ..fixture.config()
+        })
+    };
+
+    // every file alone is 0 kib, their total isn't
+    assert_eq!(total(None, Rounding::Truncate, false), "1800 b\n");
+    assert_eq!(total(Some(Unit::KB), Rounding::Truncate, false), "1 kib\n");
+    assert_eq!(total(Some(Unit::KB), Rounding::Nearest, false), "2 kib\n");
+    assert_eq!(
+        total(Some(Unit::Auto), Rounding::Truncate, true),
+        "1800 b (1.8 kib)\n"
+    );
+}
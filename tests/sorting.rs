@@ -0,0 +1,75 @@
+mod common;
+
+use common::{Fixture, names};
+use fview::config::{Config, SortKey};
+
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+/// A fixture where name, size and modified time each give a different order
+/// by name: a b c, by size: b c a, by modified time: c a b
+fn fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for (name, size, age) in [("a", 300, 20), ("b", 100, 10), ("c", 200, 30)] {
+        let path = fixture.file(name, size);
+        let modified = SystemTime::now() - Duration::from_secs(age * 60);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+    fixture
+}
+
+fn sorted(fixture: &Fixture, sort: SortKey, reversed: bool) -> Vec<String> {
+    names(&Config {
+        sort: Some(sort),
+        reversed,
+        ..fixture.config()
+    })
+}
+
+#[test]
+fn reverse_flips_every_sort_key() {
+    let fixture = fixture();
+
+    for (sort, expected) in [
+        (SortKey::Name, ["a", "b", "c"]),
+        (SortKey::Size, ["b", "c", "a"]),
+        (SortKey::Modified, ["c", "a", "b"]),
+    ] {
+        assert_eq!(sorted(&fixture, sort, false), expected, "{:?}", sort);
+
+        let mut reversed = expected;
+        reversed.reverse();
+        assert_eq!(
+            sorted(&fixture, sort, true),
+            reversed,
+            "{:?} reversed",
+            sort
+        );
+    }
+}
+
+#[test]
+fn dirs_first_keeps_directories_on_top_when_reversed() {
+    let fixture = Fixture::new();
+    fixture.dir("d1");
+    fixture.dir("d2");
+    fixture.file("f1", 1);
+    fixture.file("f2", 1);
+
+    let dirs_first = |reversed| {
+        names(&Config {
+            sort: Some(SortKey::Name),
+            dirs_first: true,
+            reversed,
+            ..fixture.config()
+        })
+    };
+
+    assert_eq!(dirs_first(false), ["d1", "d2", "f1", "f2"]);
+    assert_eq!(dirs_first(true), ["d2", "d1", "f2", "f1"]);
+}